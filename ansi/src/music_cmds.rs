@@ -1,14 +1,42 @@
 use ansiart::ansiplay::{
     rodio::{OutputStream, Sink},
-    Music, Player,
+    AudioOutput, Melody, MidiRenderer, Mixer, Music, Player, Renderer, Voice, Waveform,
 };
 use ansiart::AnsiParser;
-use clap::{AppSettings, Parser};
+use clap::{AppSettings, Args, Parser};
 use std::{
+    fs,
     io::{self, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
+#[derive(Debug, Args)]
+pub struct VoiceArgs {
+    /// The waveform to play notes with
+    #[clap(short = 'w', long, possible_values = Waveform::NAMES, default_value = "square")]
+    waveform: String,
+    /// The volume, from 0 to 1
+    #[clap(short = 'v', long, default_value = "0.1")]
+    volume: f32,
+    /// Seeds the random variations of sound codes, and noise, so that they
+    /// sound the same every time
+    #[clap(long, value_name = "seed")]
+    seed: Option<u64>,
+}
+
+impl VoiceArgs {
+    fn player(&self) -> Player {
+        let mut player = Player::with_voice(Voice {
+            waveform: Waveform::from_name(&self.waveform).unwrap_or_default(),
+            volume: self.volume,
+        });
+        if let Some(seed) = self.seed {
+            player.set_seed(seed);
+        }
+        player
+    }
+}
+
 #[derive(Debug, Parser)]
 pub enum MusicCommand {
     /// Interprets a string an plays the sequence.
@@ -19,6 +47,8 @@ pub enum MusicCommand {
         show: bool,
         #[clap(value_name = "input")]
         string: String,
+        #[clap(flatten)]
+        voice: VoiceArgs,
     },
     /// Plays ANSI Music.
     #[clap(setting(AppSettings::ArgRequiredElseHelp))]
@@ -28,10 +58,51 @@ pub enum MusicCommand {
         show: bool,
         #[clap(required = true, parse(from_os_str))]
         files: Vec<PathBuf>,
+        #[clap(flatten)]
+        voice: VoiceArgs,
+    },
+    /// Plays the ANSI Music in several files at once, each on its own channel.
+    #[clap(setting(AppSettings::ArgRequiredElseHelp))]
+    Mix {
+        /// The number of channels which can play at once
+        #[clap(short = 'n', long, default_value = "4", value_name = "voices")]
+        voices: usize,
+        #[clap(required = true, parse(from_os_str))]
+        files: Vec<PathBuf>,
+        #[clap(flatten)]
+        voice: VoiceArgs,
+    },
+    /// Renders the ANSI Music in a file to a WAV file, or a MIDI file.
+    #[clap(setting(AppSettings::ArgRequiredElseHelp))]
+    Export {
+        /// Writes a Standard MIDI File rather than a WAV file
+        #[clap(short = 'm', long)]
+        midi: bool,
+        /// Sample rate of the WAV file
+        #[clap(short = 'r', long, default_value = "48000")]
+        rate: u32,
+        #[clap(required = true, parse(from_os_str))]
+        file: PathBuf,
+        #[clap(required = true, parse(from_os_str))]
+        output: PathBuf,
+        #[clap(flatten)]
+        voice: VoiceArgs,
+    },
+    /// Converts a MIDI file, or a file of MML, to an ANSI Music sequence.
+    #[clap(setting(AppSettings::ArgRequiredElseHelp))]
+    Import {
+        #[clap(required = true, parse(from_os_str))]
+        file: PathBuf,
+        /// Writes the sequence to a file rather than stdout
+        #[clap(short = 'o', long, parse(from_os_str))]
+        output: Option<PathBuf>,
     },
     /// Shows all parsed ANSI Music sequences.
     #[clap(setting(AppSettings::ArgRequiredElseHelp))]
     Show {
+        /// Reports problems in each sequence, rather than showing them
+        #[clap(short = 'l', long)]
+        lint: bool,
         #[clap(required = true, parse(from_os_str))]
         files: Vec<PathBuf>,
     },
@@ -50,9 +121,31 @@ fn play_music(player: &mut Player, music: Music, show: bool, sink: &Sink) {
     }
 }
 
+// Reads a melody from a MIDI file, recognised by its extension, or MML
+fn read_melody(path: &Path) -> Result<Melody, String> {
+    let bytes = fs::read(path).map_err(|_| {
+        format!(
+            "An error occured whilst attempting to read {}",
+            path.to_string_lossy()
+        )
+    })?;
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+    let melody = match extension.as_deref() {
+        Some("mid" | "midi") => Melody::from_midi(&bytes),
+        _ => Melody::from_mml(&String::from_utf8_lossy(&bytes)),
+    };
+    melody.map_err(|err| err.to_string())
+}
+
 pub fn music_cmds(music_cmd: MusicCommand) {
     match music_cmd {
-        MusicCommand::Parse { show, string } => {
+        MusicCommand::Parse {
+            show,
+            string,
+            voice,
+        } => {
             match OutputStream::try_default() {
                 Err(_err) => {
                     eprintln!("An error occured whilst attempting to create an audio stream");
@@ -62,14 +155,14 @@ pub fn music_cmds(music_cmd: MusicCommand) {
                         eprintln!("An error occured whilst attempting to create an audio stream")
                     }
                     Ok(ref sink) => {
-                        let mut player = Player::new();
+                        let mut player = voice.player();
                         let music = Music::from(string.as_str());
                         play_music(&mut player, music, show, sink);
                     }
                 },
             };
         }
-        MusicCommand::Play { files, show } => {
+        MusicCommand::Play { files, show, voice } => {
             match OutputStream::try_default() {
                 Err(_err) => {
                     eprintln!("An error occured whilst attempting to create an audio stream");
@@ -80,7 +173,7 @@ pub fn music_cmds(music_cmd: MusicCommand) {
                     }
                     Ok(ref sink) => {
                         for path in files {
-                            let mut player = Player::new();
+                            let mut player = voice.player();
                             match AnsiParser::read(&path) {
                                 Err(_err) => eprintln!(
                                     "An error occured whilst attempting to read {}",
@@ -99,9 +192,15 @@ pub fn music_cmds(music_cmd: MusicCommand) {
                 },
             };
         }
-        MusicCommand::Show { files } => {
-            for path in files {
-                match AnsiParser::read(&path) {
+        MusicCommand::Mix {
+            voices,
+            files,
+            voice,
+        } => {
+            let mut mixer = Mixer::new(AudioOutput::try_default(), voices);
+            for (channel, path) in files.iter().enumerate() {
+                mixer.set_player(channel, voice.player());
+                match AnsiParser::read(path) {
                     Err(_err) => eprintln!(
                         "An error occured whilst attempting to read {}",
                         path.to_string_lossy()
@@ -109,7 +208,87 @@ pub fn music_cmds(music_cmd: MusicCommand) {
                     Ok(parser) => {
                         for sequence in parser {
                             if let ansiart::Sequence::Music(music) = sequence {
+                                if let Err(err) = mixer.play(channel, music) {
+                                    eprintln!("{}: {err}", path.to_string_lossy());
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            mixer.sleep_until_end();
+        }
+        MusicCommand::Export {
+            midi,
+            rate,
+            file,
+            output,
+            voice,
+        } => match AnsiParser::read(&file) {
+            Err(_err) => eprintln!(
+                "An error occured whilst attempting to read {}",
+                file.to_string_lossy()
+            ),
+            Ok(parser) => {
+                let music = parser.filter_map(|sequence| match sequence {
+                    ansiart::Sequence::Music(music) => Some(music),
+                    _ => None,
+                });
+                let result = if midi {
+                    let mut renderer = MidiRenderer::with_player(voice.player());
+                    music.for_each(|music| renderer.render(music));
+                    renderer.write_midi(&output)
+                } else {
+                    let mut renderer = Renderer::with_player(voice.player(), rate);
+                    music.for_each(|music| renderer.render(music));
+                    renderer.write_wav(&output)
+                };
+                if let Err(err) = result {
+                    eprintln!("{err}");
+                }
+            }
+        },
+        MusicCommand::Import { file, output } => match read_melody(&file) {
+            Err(err) => eprintln!("{err}"),
+            Ok(melody) => {
+                let result = match output {
+                    Some(ref path) => fs::write(path, melody.to_sequence()),
+                    None => io::stdout().write_all(&melody.to_sequence()),
+                };
+                if result.is_err() {
+                    eprintln!("An error occured whilst attempting to write the sequence");
+                }
+            }
+        },
+        MusicCommand::Show { lint, files } => {
+            for path in files {
+                match AnsiParser::read(&path) {
+                    Err(_err) => eprintln!(
+                        "An error occured whilst attempting to read {}",
+                        path.to_string_lossy()
+                    ),
+                    Ok(parser) => {
+                        let music = parser.filter_map(|sequence| match sequence {
+                            ansiart::Sequence::Music(music) => Some(music),
+                            _ => None,
+                        });
+                        for (index, music) in music.enumerate() {
+                            if !lint {
                                 println!("{music}");
+                                continue;
+                            }
+                            for error in music.errors() {
+                                let level = if error.is_warning() {
+                                    "warning"
+                                } else {
+                                    "error"
+                                };
+                                println!(
+                                    "{}: sequence {}: {level}: {error}",
+                                    path.to_string_lossy(),
+                                    index + 1
+                                );
                             }
                         }
                     }
@@ -1,29 +1,77 @@
-use ansiart::AnsiParser;
-use ansiterm::{terminal, TerminalEvent};
+use ansiart::{atascii::AtasciiDecoder, petscii::PetsciiDecoder, AnsiParser};
+use ansiterm::{
+    terminal::{Palette, Profile, Terminal},
+    BuilderError, CrtSettings, TerminalBuilder, TerminalEvent, TerminalWindow, VirtualKeyCode,
+};
 use clap::{AppSettings, Parser};
-use std::{fs, path::PathBuf};
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process,
+};
 use stdin_receiver::StdInReceiver;
 
 #[derive(Debug, Parser)]
 pub enum TermCommand {
-    /// Shows ANSI Art in a simulated terminal
+    /// Shows ANSI Art in a simulated terminal, use space to pause, +/- to
+    /// change the baud rate, right to skip to the end, R to restart, N/P to
+    /// move between files, and Alt+Enter to toggle fullscreen, drag to select
+    /// and Ctrl+C to copy as text, or Ctrl+Shift+C to copy as ANSI
     #[clap(setting(AppSettings::ArgRequiredElseHelp))]
     Show {
         /// Throttle input with at a specific baud rate
         #[clap(short = 'b', default_value = "14400", value_name = "baud rate")]
         baud_rate: usize,
-        /// Terminal width in columns
-        #[clap(short = 'c', default_value = "80", value_name = "columns")]
-        columns: usize,
-        /// Terminal height in rows
-        #[clap(short = 'r', default_value = "25", value_name = "rows")]
-        rows: usize,
+        /// Terminal width in columns, 80 or 40 for PETSCII
+        #[clap(short = 'c', value_name = "columns")]
+        columns: Option<usize>,
+        /// Terminal height in rows, 25 or 24 for ATASCII
+        #[clap(short = 'r', value_name = "rows")]
+        rows: Option<usize>,
         /// Ice colors
         #[clap(short = 'i')]
         ice_colors: bool,
         /// Scale
         #[clap(short = 's', default_value = "2", value_name = "scale")]
         scale: usize,
+        /// Correct the aspect ratio as on a 4:3 display, using non-integer
+        /// scaling
+        #[clap(short = 'a')]
+        aspect_correction: bool,
+        /// Palette file, either 48 bytes of 6 bit VGA values or an XBin, ADF, or IDF file
+        #[clap(short = 'p', parse(from_os_str), value_name = "palette")]
+        palette: Option<PathBuf>,
+        /// The format of the files, which follow the conventions of another
+        /// computer, chosen by the extension of the first file if not given,
+        /// .asc for Amiga ANSI and .seq for PETSCII
+        #[clap(long, possible_values = FORMATS, value_name = "format")]
+        format: Option<String>,
+        /// Play ANSI Music silently
+        #[clap(short = 'm', long)]
+        mute: bool,
+        /// Show the music that's playing on the bottom line
+        #[clap(long)]
+        status: bool,
+        /// Simulate a CRT display
+        #[clap(long = "crt")]
+        crt: bool,
+        /// How much darker the gaps between scanlines are on the CRT, from 0 to 1
+        #[clap(long, default_value = "0.5", value_name = "amount")]
+        scanlines: f32,
+        /// How much each pixel bleeds into its neighbours on the CRT, from 0 to 1
+        #[clap(long, default_value = "0.25", value_name = "amount")]
+        bloom: f32,
+        /// How slowly the phosphor fades on the CRT, from 0 to 1
+        #[clap(long, default_value = "0.5", value_name = "amount")]
+        persistence: f32,
+        /// How curved the screen of the CRT is, from 0 to 1
+        #[clap(long, default_value = "0.1", value_name = "amount")]
+        curvature: f32,
+        /// Directory to write screenshots (F12) and recordings (F11) to, both
+        /// include the selection and status line
+        #[clap(short = 'o', parse(from_os_str), value_name = "directory")]
+        output_dir: Option<PathBuf>,
         #[clap(required = true, parse(from_os_str))]
         files: Vec<PathBuf>,
     },
@@ -33,22 +81,167 @@ pub enum TermCommand {
         /// Throttle input with at a specific baud rate
         #[clap(short = 'b', default_value = "14400", value_name = "baud rate")]
         baud_rate: usize,
-        /// Terminal width in columns
-        #[clap(short = 'c', default_value = "80", value_name = "columns")]
-        columns: usize,
-        /// Terminal height in rows
-        #[clap(short = 'r', default_value = "25", value_name = "rows")]
-        rows: usize,
+        /// Terminal width in columns, 80 or 40 for PETSCII
+        #[clap(short = 'c', value_name = "columns")]
+        columns: Option<usize>,
+        /// Terminal height in rows, 25 or 24 for ATASCII
+        #[clap(short = 'r', value_name = "rows")]
+        rows: Option<usize>,
         /// Ice colors
         #[clap(short = 'i')]
         ice_colors: bool,
         /// Scale
         #[clap(short = 's', default_value = "2", value_name = "scale")]
         scale: usize,
+        /// Correct the aspect ratio as on a 4:3 display, using non-integer
+        /// scaling
+        #[clap(short = 'a')]
+        aspect_correction: bool,
+        /// Palette file, either 48 bytes of 6 bit VGA values or an XBin, ADF, or IDF file
+        #[clap(short = 'p', parse(from_os_str), value_name = "palette")]
+        palette: Option<PathBuf>,
+        /// The format of the input, which follows the conventions of another
+        /// computer
+        #[clap(long, possible_values = FORMATS, value_name = "format")]
+        format: Option<String>,
+        /// Play ANSI Music silently
+        #[clap(short = 'm', long)]
+        mute: bool,
+        /// Show the music that's playing on the bottom line
+        #[clap(long)]
+        status: bool,
+        /// Directory to write screenshots (F12) and recordings (F11) to, both
+        /// include the selection and status line
+        #[clap(short = 'o', parse(from_os_str), value_name = "directory")]
+        output_dir: Option<PathBuf>,
+    },
+    /// Renders a PETSCII .seq file, or an ATASCII file, as a PNG file
+    #[clap(setting(AppSettings::ArgRequiredElseHelp))]
+    ToPng {
+        /// The format of the file, chosen by its extension if not given
+        #[clap(long, possible_values = ["petscii", "atascii"], value_name = "format")]
+        format: Option<String>,
+        /// Terminal height in rows, 25 or 24 for ATASCII
+        #[clap(short = 'r', value_name = "rows")]
+        rows: Option<usize>,
+        #[clap(required = true, parse(from_os_str), value_name = "file")]
+        file: PathBuf,
+        #[clap(required = true, parse(from_os_str), value_name = "PNG file")]
+        png: PathBuf,
     },
 }
 
-pub fn term_cmds(term_cmd: TermCommand) -> ! {
+fn run<F>(window: Result<TerminalWindow, BuilderError>, parser: AnsiParser, term_event_loop: F)
+where
+    F: FnMut(&mut AnsiParser, TerminalEvent, &mut Terminal),
+{
+    match window {
+        Ok(window) => window.run(parser, term_event_loop),
+        Err(err) => {
+            eprintln!("{err}");
+            process::exit(1);
+        }
+    }
+}
+
+fn read_file(parser: &mut AnsiParser, path: &Path) {
+    match fs::read(path) {
+        Ok(bytes) => parser.input(bytes),
+        Err(_) => {
+            eprintln!(
+                "An error occured whilst attempting to read {}",
+                path.to_string_lossy()
+            )
+        }
+    }
+}
+
+/// The formats which can be given to `--format`
+const FORMATS: [&str; 4] = ["ansi", "amiga", "petscii", "atascii"];
+
+// Returns the profile for the format named on the command line, or the one
+// conventionally used by files with the extension of `path`
+fn select_profile(format: Option<String>, path: Option<&Path>) -> Profile {
+    match format.as_deref() {
+        Some("amiga") => Profile::Amiga,
+        Some("petscii") => Profile::Petscii,
+        Some("atascii") => Profile::Atascii,
+        Some(_) => Profile::Dos,
+        None => path
+            .and_then(Path::extension)
+            .and_then(|extension| Profile::from_extension(&extension.to_string_lossy()))
+            .unwrap_or_default(),
+    }
+}
+
+// Constructs a parser which passes every byte through for character sets
+// which don't use escape sequences
+fn parser(baud_rate: usize, profile: Profile) -> AnsiParser {
+    let mut parser = AnsiParser::with_baud(baud_rate);
+    parser.set_literal(!profile.is_ansi());
+    parser
+}
+
+fn read_palette(path: Option<PathBuf>, default: Palette) -> Option<Palette> {
+    match path {
+        Some(path) => match Palette::read(&path) {
+            Ok(palette) => Some(palette),
+            Err(err) => {
+                eprintln!("{err}");
+                None
+            }
+        },
+        None => Some(default),
+    }
+}
+
+// Returns the palette embedded in an XBin, ADF, or IDF file, or `default`
+fn file_palette(path: &Path, default: &Palette) -> Palette {
+    match Palette::read_embedded(path) {
+        Ok(Some(palette)) => palette,
+        Ok(None) => default.clone(),
+        Err(err) => {
+            eprintln!("{err}");
+            default.clone()
+        }
+    }
+}
+
+fn to_png(format: Option<String>, rows: Option<usize>, file: &Path, png: &Path) {
+    let bytes = match fs::read(file) {
+        Ok(bytes) => bytes,
+        Err(_) => {
+            eprintln!(
+                "An error occured whilst attempting to read {}",
+                file.to_string_lossy()
+            );
+            return;
+        }
+    };
+    let profile = select_profile(format, Some(file));
+    let rows = rows.unwrap_or_else(|| profile.rows());
+    let result = match profile {
+        Profile::Petscii => {
+            let mut decoder = PetsciiDecoder::new(rows);
+            decoder.input(&bytes);
+            decoder.canvas().write_png(png)
+        }
+        Profile::Atascii => {
+            let mut decoder = AtasciiDecoder::new(rows);
+            decoder.input(&bytes);
+            decoder.canvas().write_png(png)
+        }
+        _ => {
+            eprintln!("Unrecognised format, use --format");
+            return;
+        }
+    };
+    if let Err(err) = result {
+        eprintln!("{err}");
+    }
+}
+
+pub fn term_cmds(term_cmd: TermCommand) {
     match term_cmd {
         TermCommand::Show {
             baud_rate,
@@ -56,21 +249,64 @@ pub fn term_cmds(term_cmd: TermCommand) -> ! {
             rows,
             ice_colors,
             scale,
+            aspect_correction,
+            palette,
+            format,
+            mute,
+            status,
+            crt,
+            scanlines,
+            bloom,
+            persistence,
+            curvature,
+            output_dir,
             files,
         } => {
-            let mut parser = AnsiParser::with_baud(baud_rate);
-            for path in files {
-                match fs::read(&path) {
-                    Ok(bytes) => parser.input(bytes),
-                    Err(_) => {
-                        eprint!(
-                            "An error  occured whilst attempting to read {}",
-                            path.to_string_lossy()
-                        )
-                    }
+            let profile = select_profile(format, Some(&files[0]));
+            let palette = match read_palette(palette, profile.palette()) {
+                Some(palette) => palette,
+                None => return,
+            };
+            let mut parser = parser(baud_rate, profile);
+            let mut index = 0;
+            read_file(&mut parser, &files[index]);
+            let output_dir = output_dir.unwrap_or_else(|| match files[index].parent() {
+                Some(parent) => parent.to_owned(),
+                None => PathBuf::from("."),
+            });
+            let window = TerminalBuilder::new()
+                .profile(profile)
+                .size(
+                    columns.unwrap_or_else(|| profile.columns()),
+                    rows.unwrap_or_else(|| profile.rows()),
+                )
+                .scale(scale)
+                .ice_colors(ice_colors)
+                .aspect_correction(aspect_correction)
+                .palette(file_palette(&files[index], &palette))
+                .audio(!mute)
+                .status_line(status)
+                .crt(crt.then_some(CrtSettings {
+                    scanlines,
+                    bloom,
+                    persistence,
+                    curvature,
+                }))
+                .capture_dir(output_dir)
+                .build();
+            run(window, parser, move |parser, event, term| {
+                if let TerminalEvent::Keypress { key_code, .. } = event {
+                    index = match key_code {
+                        VirtualKeyCode::N if index + 1 < files.len() => index + 1,
+                        VirtualKeyCode::P if index > 0 => index - 1,
+                        _ => return,
+                    };
+                    *parser = self::parser(baud_rate, profile);
+                    read_file(parser, &files[index]);
+                    term.set_palette(file_palette(&files[index], &palette));
+                    term.reset();
                 }
-            }
-            terminal(parser, columns, rows, scale, ice_colors, |_, _, _| {});
+            });
         }
         TermCommand::StdIn {
             baud_rate,
@@ -78,23 +314,53 @@ pub fn term_cmds(term_cmd: TermCommand) -> ! {
             rows,
             ice_colors,
             scale,
+            aspect_correction,
+            palette,
+            format,
+            mute,
+            status,
+            output_dir,
         } => {
-            let parser = AnsiParser::with_baud(baud_rate);
+            let profile = select_profile(format, None);
+            let palette = match read_palette(palette, profile.palette()) {
+                Some(palette) => palette,
+                None => return,
+            };
+            let parser = parser(baud_rate, profile);
             let std_reciever = StdInReceiver::default();
-            terminal(
-                parser,
-                columns,
-                rows,
-                scale,
-                ice_colors,
-                move |parser, event, _| {
-                    if let TerminalEvent::RedrawRequested = event {
-                        if let Some(bytes) = std_reciever.recv().expect("Thread error") {
-                            parser.input(bytes)
-                        }
+            let window = TerminalBuilder::new()
+                .profile(profile)
+                .size(
+                    columns.unwrap_or_else(|| profile.columns()),
+                    rows.unwrap_or_else(|| profile.rows()),
+                )
+                .scale(scale)
+                .ice_colors(ice_colors)
+                .aspect_correction(aspect_correction)
+                .palette(palette)
+                .audio(!mute)
+                .status_line(status)
+                .playback_controls(false)
+                .capture_dir(output_dir.unwrap_or_else(|| PathBuf::from(".")))
+                .build();
+            run(window, parser, move |parser, event, term| {
+                if let TerminalEvent::RedrawRequested = event {
+                    if let Some(bytes) = term.take_responses() {
+                        let mut stdout = io::stdout();
+                        stdout.write_all(&bytes).ok();
+                        stdout.flush().ok();
                     }
-                },
-            );
+                    if let Some(bytes) = std_reciever.recv().expect("Thread error") {
+                        parser.input(bytes)
+                    }
+                }
+            });
         }
+        TermCommand::ToPng {
+            format,
+            rows,
+            file,
+            png,
+        } => to_png(format, rows, &file, &png),
     }
 }
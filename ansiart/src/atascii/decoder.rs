@@ -0,0 +1,166 @@
+use crate::{
+    atascii::{control, font, Control, BACKGROUND, FOREGROUND},
+    canvas::{Canvas, CanvasCell},
+};
+use codepage437::ascii;
+
+const COLUMNS: usize = 40;
+const ROWS: usize = 24;
+const TAB_WIDTH: usize = 8;
+
+/// Decodes an ATASCII stream onto a [Canvas] the size of the Atari's text
+/// playfield, in its two colours
+pub struct AtasciiDecoder {
+    canvas: Canvas,
+    column: usize,
+    // Equal to the number of rows when the next character scrolls the screen
+    row: usize,
+    tabs: Vec<bool>,
+    escaped: bool,
+}
+
+impl Default for AtasciiDecoder {
+    fn default() -> Self {
+        AtasciiDecoder::new(ROWS)
+    }
+}
+
+impl AtasciiDecoder {
+    /// Constructs a decoder for a 40 column screen of `rows`
+    pub fn new(rows: usize) -> AtasciiDecoder {
+        AtasciiDecoder {
+            canvas: Canvas::new(COLUMNS, rows.max(1), vec![font()], AtasciiDecoder::blank()),
+            column: 0,
+            row: 0,
+            tabs: (0..COLUMNS)
+                .map(|column| column > 0 && column % TAB_WIDTH == 0)
+                .collect(),
+            escaped: false,
+        }
+    }
+
+    pub fn canvas(&self) -> &Canvas {
+        &self.canvas
+    }
+
+    /// Decodes `bytes`, every value is a character in ATASCII so the whole of
+    /// the input is decoded
+    pub fn input(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            if self.escaped {
+                self.escaped = false;
+                self.draw(*byte);
+                continue;
+            }
+            match control(*byte) {
+                Some(control) => self.control(control),
+                None => self.draw(*byte),
+            }
+        }
+    }
+
+    fn blank() -> CanvasCell {
+        CanvasCell {
+            code: ascii::SPACE,
+            fg: FOREGROUND,
+            bg: BACKGROUND,
+            font: 0,
+        }
+    }
+
+    fn control(&mut self, control: Control) {
+        let rows = self.canvas.rows;
+        match control {
+            Control::Escape => self.escaped = true,
+            // The cursor wraps around the edges of the screen
+            Control::CursorUp => self.row = (self.row.min(rows - 1) + rows - 1) % rows,
+            Control::CursorDown => self.row = (self.row.min(rows - 1) + 1) % rows,
+            Control::CursorLeft => {
+                self.column = (self.column + COLUMNS - 1) % COLUMNS;
+                self.row = self.row.min(rows - 1);
+            }
+            Control::CursorRight => {
+                self.column = (self.column + 1) % COLUMNS;
+                self.row = self.row.min(rows - 1);
+            }
+            Control::Clear => {
+                self.canvas.clear(AtasciiDecoder::blank());
+                (self.column, self.row) = (0, 0);
+            }
+            Control::Backspace => {
+                if self.column > 0 {
+                    self.column -= 1;
+                    if let Some(cell) = self.canvas.get_mut(self.column, self.row) {
+                        *cell = AtasciiDecoder::blank();
+                    }
+                }
+            }
+            Control::Tab => {
+                self.column = (self.column + 1..COLUMNS)
+                    .find(|column| self.tabs[*column])
+                    .unwrap_or(COLUMNS - 1);
+            }
+            Control::EndOfLine => self.next_line(),
+            Control::DeleteLine => {
+                if self.row < rows {
+                    let cells = &mut self.canvas.cells_mut()[self.row * COLUMNS..];
+                    cells.rotate_left(COLUMNS);
+                    let last = cells.len() - COLUMNS;
+                    cells[last..].fill(AtasciiDecoder::blank());
+                }
+            }
+            Control::InsertLine => {
+                if self.row < rows {
+                    let cells = &mut self.canvas.cells_mut()[self.row * COLUMNS..];
+                    cells.rotate_right(COLUMNS);
+                    cells[..COLUMNS].fill(AtasciiDecoder::blank());
+                }
+            }
+            Control::ClearTab => self.tabs[self.column] = false,
+            Control::SetTab => self.tabs[self.column] = true,
+            Control::Bell => {}
+            Control::DeleteCharacter | Control::InsertCharacter => {
+                if self.row < rows {
+                    let start = self.row * COLUMNS + self.column;
+                    let end = (self.row + 1) * COLUMNS;
+                    let cells = &mut self.canvas.cells_mut()[start..end];
+                    if control == Control::DeleteCharacter {
+                        cells.rotate_left(1);
+                        cells[cells.len() - 1] = AtasciiDecoder::blank();
+                    } else {
+                        cells.rotate_right(1);
+                        cells[0] = AtasciiDecoder::blank();
+                    }
+                }
+            }
+        }
+    }
+
+    fn next_line(&mut self) {
+        self.column = 0;
+        if self.row + 1 < self.canvas.rows {
+            self.row += 1;
+        } else {
+            self.canvas.scroll_up(AtasciiDecoder::blank());
+            self.row = self.canvas.rows - 1;
+        }
+    }
+
+    fn draw(&mut self, byte: u8) {
+        if self.row == self.canvas.rows {
+            self.canvas.scroll_up(AtasciiDecoder::blank());
+            self.row -= 1;
+        }
+        if let Some(cell) = self.canvas.get_mut(self.column, self.row) {
+            cell.code = byte;
+        }
+        if self.column + 1 < COLUMNS {
+            self.column += 1;
+        } else {
+            // Scrolling waits for the next character, so that the last line
+            // can be filled without moving the screen
+            self.column = 0;
+            self.row += 1;
+        }
+    }
+}
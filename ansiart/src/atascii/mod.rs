@@ -0,0 +1,85 @@
+//! Control codes and conversions for the Atari 8-bit computers' character set
+
+mod decoder;
+use codepage437::{raw, Font};
+pub use decoder::AtasciiDecoder;
+use ega_palette::Rgb;
+
+pub const ESCAPE: u8 = 0x1b;
+pub const CURSOR_UP: u8 = 0x1c;
+pub const CURSOR_DOWN: u8 = 0x1d;
+pub const CURSOR_LEFT: u8 = 0x1e;
+pub const CURSOR_RIGHT: u8 = 0x1f;
+pub const CLEAR: u8 = 0x7d;
+pub const BACKSPACE: u8 = 0x7e;
+pub const TAB: u8 = 0x7f;
+pub const END_OF_LINE: u8 = 0x9b;
+pub const DELETE_LINE: u8 = 0x9c;
+pub const INSERT_LINE: u8 = 0x9d;
+pub const CLEAR_TAB: u8 = 0x9e;
+pub const SET_TAB: u8 = 0x9f;
+pub const BELL: u8 = 0xfd;
+pub const DELETE_CHARACTER: u8 = 0xfe;
+pub const INSERT_CHARACTER: u8 = 0xff;
+
+/// Approximately the default background of the Atari's text playfield
+pub const BACKGROUND: Rgb = [0x1a, 0x52, 0x98];
+/// Approximately the default brightness of the characters on the playfield,
+/// which are always the same hue as the background
+pub const FOREGROUND: Rgb = [0x6c, 0xa6, 0xec];
+
+/// Returns the bundled ATASCII font, rearranged so that each character is at
+/// the index of its code, as the file has the inverse characters, those with
+/// the high bit set, in its first half
+pub fn font() -> Font {
+    let mut font = Font::try_from(raw::ATASCII_F08.as_ref()).expect("font");
+    let half = font.bytes.len() / 2;
+    font.bytes.rotate_left(half);
+    font
+}
+
+/// What an ATASCII control code does
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Control {
+    /// Draws the next character, even if it's a control code
+    Escape,
+    CursorUp,
+    CursorDown,
+    CursorLeft,
+    CursorRight,
+    Clear,
+    Backspace,
+    Tab,
+    EndOfLine,
+    DeleteLine,
+    InsertLine,
+    ClearTab,
+    SetTab,
+    Bell,
+    DeleteCharacter,
+    InsertCharacter,
+}
+
+/// Returns what a control code does, or `None` for printable characters
+pub fn control(byte: u8) -> Option<Control> {
+    let control = match byte {
+        ESCAPE => Control::Escape,
+        CURSOR_UP => Control::CursorUp,
+        CURSOR_DOWN => Control::CursorDown,
+        CURSOR_LEFT => Control::CursorLeft,
+        CURSOR_RIGHT => Control::CursorRight,
+        CLEAR => Control::Clear,
+        BACKSPACE => Control::Backspace,
+        TAB => Control::Tab,
+        END_OF_LINE => Control::EndOfLine,
+        DELETE_LINE => Control::DeleteLine,
+        INSERT_LINE => Control::InsertLine,
+        CLEAR_TAB => Control::ClearTab,
+        SET_TAB => Control::SetTab,
+        BELL => Control::Bell,
+        DELETE_CHARACTER => Control::DeleteCharacter,
+        INSERT_CHARACTER => Control::InsertCharacter,
+        _ => return None,
+    };
+    Some(control)
+}
@@ -0,0 +1,17 @@
+use std::{error, fmt};
+
+/// Custom error used when rendering a [crate::canvas::Canvas]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CanvasError {
+    CannotWriteImage,
+}
+
+impl fmt::Display for CanvasError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CanvasError::CannotWriteImage => write!(f, "Cannot write image"),
+        }
+    }
+}
+
+impl error::Error for CanvasError {}
@@ -0,0 +1,113 @@
+mod canvas_error;
+pub use canvas_error::CanvasError;
+use codepage437::{DrawFont, Font};
+use ega_palette::Rgb;
+use image::{ImageBuffer, Rgba};
+use std::path::Path;
+
+/// A character on a [Canvas], drawn with one of the canvas's fonts
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CanvasCell {
+    pub code: u8,
+    pub fg: Rgb,
+    pub bg: Rgb,
+    /// The index of the font in [Canvas::fonts]
+    pub font: usize,
+}
+
+/// A grid of characters decoded from a stream of another computer's
+/// character set, which can be rendered as an image
+#[derive(Clone, Debug)]
+pub struct Canvas {
+    pub columns: usize,
+    pub rows: usize,
+    cells: Vec<CanvasCell>,
+    fonts: Vec<Font>,
+}
+
+impl Canvas {
+    /// Constructs a canvas filled with `blank`, all of the `fonts` should
+    /// be the same size
+    pub fn new(columns: usize, rows: usize, fonts: Vec<Font>, blank: CanvasCell) -> Canvas {
+        Canvas {
+            columns,
+            rows,
+            cells: vec![blank; columns * rows],
+            fonts,
+        }
+    }
+
+    pub fn fonts(&self) -> &[Font] {
+        &self.fonts
+    }
+
+    pub fn cells(&self) -> &[CanvasCell] {
+        &self.cells
+    }
+
+    pub fn cells_mut(&mut self) -> &mut [CanvasCell] {
+        &mut self.cells
+    }
+
+    pub fn get(&self, column: usize, row: usize) -> Option<&CanvasCell> {
+        if column < self.columns {
+            self.cells.get(row * self.columns + column)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, column: usize, row: usize) -> Option<&mut CanvasCell> {
+        if column < self.columns {
+            self.cells.get_mut(row * self.columns + column)
+        } else {
+            None
+        }
+    }
+
+    /// Fills every cell with `blank`
+    pub fn clear(&mut self, blank: CanvasCell) {
+        self.cells.fill(blank);
+    }
+
+    /// Moves every row up, filling the bottom row with `blank`
+    pub fn scroll_up(&mut self, blank: CanvasCell) {
+        self.cells.drain(..self.columns);
+        self.cells.resize(self.columns * self.rows, blank);
+    }
+
+    /// Returns the size of the rendered image in pixels
+    pub fn dimensions(&self) -> (usize, usize) {
+        match self.fonts.first() {
+            Some(font) => (self.columns * font.width, self.rows * font.height),
+            None => (0, 0),
+        }
+    }
+
+    /// Renders every cell as RGBA data
+    pub fn to_rgba(&self) -> Vec<u8> {
+        let (width, height) = self.dimensions();
+        let mut rgba = vec![0; width * height * 4];
+        for (index, cell) in self.cells.iter().enumerate() {
+            if let Some(font) = self.fonts.get(cell.font) {
+                let [r, g, b] = cell.fg;
+                let fg = [r, g, b, 255];
+                let [r, g, b] = cell.bg;
+                let bg = [r, g, b, 255];
+                let x = index % self.columns * font.width;
+                let y = index / self.columns * font.height;
+                rgba.draw_font(x, y, font.width, width, &font.to_bytes(cell.code, &fg, &bg));
+            }
+        }
+        rgba
+    }
+
+    /// Renders the canvas as a PNG file
+    pub fn write_png(&self, path: impl AsRef<Path>) -> Result<(), CanvasError> {
+        let (width, height) = self.dimensions();
+        let buffer: ImageBuffer<Rgba<u8>, Vec<u8>> =
+            ImageBuffer::from_vec(width as u32, height as u32, self.to_rgba())
+                .ok_or(CanvasError::CannotWriteImage)?;
+        buffer.save(path).map_err(|_| CanvasError::CannotWriteImage)
+    }
+}
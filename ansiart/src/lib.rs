@@ -1,14 +1,29 @@
+pub mod atascii;
+pub mod canvas;
+mod osc;
+pub mod petscii;
 mod sequence_iterator;
 pub use ansiplay;
 use ansiplay::Music;
 pub use codepage437;
 use codepage437::ascii;
 pub use ega_palette;
+use osc::parse_osc;
 pub use sauce;
 use sauce::{Sauce, COMNT_HEAD, SAUCE_HEAD};
 use sequence_iterator::IntoNumberSequenceIter;
 use std::{fs, io, path::Path};
 
+/// Standard baud rates that playback can be stepped through.
+pub const BAUD_RATES: [usize; 12] = [
+    300, 600, 1200, 2400, 4800, 9600, 14400, 19200, 28800, 38400, 57600, 115200,
+];
+
+// The number of bytes received in a 60th of a second at `baud_rate`
+fn bytes_per_frame(baud_rate: usize) -> usize {
+    ((baud_rate as f32 / 8.0 / 60.0) as usize).max(1)
+}
+
 enum State {
     Literal,
     Escape,
@@ -23,6 +38,8 @@ enum State {
         comments_start: usize,
     },
     Music(usize),
+    OperatingSystemCommand(usize),
+    OperatingSystemCommandEscape(usize),
 }
 
 #[derive(Clone, Debug)]
@@ -40,6 +57,12 @@ pub enum Sequence {
     ResetScreenMode(usize),
     EraseDisplay(usize),
     EraseInLine(usize),
+    DeviceStatusReport(usize),
+    DeviceAttributes,
+    // DECSCUSR, `ESC[n q`, changes the shape and blinking of the cursor
+    SelectCursorStyle(usize),
+    // SyncTERM's `ESC[slot;font D`, loads one of its numbered fonts into a slot
+    FontSelection { slot: usize, font: usize },
     SelectGraphicsRendition(Vec<usize>),
     SavePosition,
     RestorePosition,
@@ -47,6 +70,10 @@ pub enum Sequence {
     TrueColourBg { r: u8, g: u8, b: u8 },
     TrueColourFg { r: u8, g: u8, b: u8 },
     Music(Music),
+    // Sets entries in the palette to new RGB values
+    SetPaletteColours(Vec<(usize, [u8; 3])>),
+    // Restores entries in the palette, or all of them when empty
+    ResetPalette(Vec<usize>),
     Unknown { bytes: Vec<u8>, terminator: u8 },
     Update,
 }
@@ -56,6 +83,12 @@ pub struct AnsiParser {
     bytes: Vec<u8>,
     position: usize,
     baud_rate: Option<usize>,
+    // The baud rate the parser was constructed with, restored on restart
+    initial_baud_rate: Option<usize>,
+    // Input before this position is parsed without throttling
+    skip_to: Option<usize>,
+    literal: bool,
+    updated: bool,
 }
 
 impl Default for AnsiParser {
@@ -65,6 +98,10 @@ impl Default for AnsiParser {
             bytes: vec![],
             position: 0,
             baud_rate: Some(14400),
+            initial_baud_rate: Some(14400),
+            skip_to: None,
+            literal: false,
+            updated: false,
         }
     }
 }
@@ -85,6 +122,7 @@ impl AnsiParser {
     pub fn with_baud(baud_rate: usize) -> AnsiParser {
         AnsiParser {
             baud_rate: Some(baud_rate),
+            initial_baud_rate: Some(baud_rate),
             ..Default::default()
         }
     }
@@ -92,12 +130,87 @@ impl AnsiParser {
     pub fn input(&mut self, mut bytes: Vec<u8>) {
         self.bytes.append(&mut bytes);
     }
+
+    /// Returns to the start of the input, so everything will be parsed again,
+    /// at the baud rate the parser was constructed with.
+    pub fn restart(&mut self) {
+        self.state = State::Literal;
+        self.position = 0;
+        self.baud_rate = self.initial_baud_rate;
+        self.skip_to = None;
+    }
+
+    /// Parses the input received so far without throttling, anything input
+    /// afterwards is throttled by the baud rate again.
+    pub fn skip_to_end(&mut self) {
+        self.skip_to = Some(self.bytes.len());
+    }
+
+    // The baud rate in effect, which is none whilst skipping
+    fn throttle(&self) -> Option<usize> {
+        match self.skip_to {
+            Some(end) if self.position < end => None,
+            _ => self.baud_rate,
+        }
+    }
+
+    pub fn baud_rate(&self) -> Option<usize> {
+        self.baud_rate
+    }
+
+    /// Sets the rate at which [Sequence::Update] is returned, `None` parses
+    /// the input without any throttling.
+    pub fn set_baud_rate(&mut self, baud_rate: Option<usize>) {
+        self.baud_rate = baud_rate;
+    }
+
+    /// Returns every byte as a [Sequence::Literal], for character sets such as
+    /// PETSCII and ATASCII which don't use escape sequences or SAUCE records.
+    pub fn set_literal(&mut self, literal: bool) {
+        self.literal = literal;
+    }
+
+    // Returns the next byte as a literal, throttled by the baud rate
+    fn next_literal(&mut self) -> Option<Sequence> {
+        let byte = *self.bytes.get(self.position)?;
+        if let Some(baud_rate) = self.throttle() {
+            if !self.updated
+                && self.position > 0
+                && self.position.is_multiple_of(bytes_per_frame(baud_rate))
+            {
+                self.updated = true;
+                return Some(Sequence::Update);
+            }
+        }
+        self.updated = false;
+        self.position += 1;
+        Some(Sequence::Literal(byte))
+    }
+
+    /// Steps up to the next of the [BAUD_RATES], beyond the fastest the
+    /// input is no longer throttled.
+    pub fn increase_baud_rate(&mut self) {
+        if let Some(baud_rate) = self.baud_rate {
+            self.baud_rate = BAUD_RATES.iter().copied().find(|rate| *rate > baud_rate);
+        }
+    }
+
+    /// Steps down to the previous of the [BAUD_RATES].
+    pub fn decrease_baud_rate(&mut self) {
+        let baud_rate = self.baud_rate.unwrap_or(usize::MAX);
+        if let Some(rate) = BAUD_RATES.iter().rev().find(|rate| **rate < baud_rate) {
+            self.baud_rate = Some(*rate);
+        }
+    }
 }
 
 impl Iterator for AnsiParser {
     type Item = Sequence;
 
     fn next(&mut self) -> Option<Self::Item> {
+        if self.literal {
+            return self.next_literal();
+        }
         loop {
             if let Some(byte) = self.bytes.get(self.position) {
                 self.position += 1;
@@ -112,6 +225,9 @@ impl Iterator for AnsiParser {
                     },
                     State::Escape => match *byte {
                         ascii::LEFT_SQUARE_BRACKET => self.state = State::Sequence(self.position),
+                        ascii::RIGHT_SQUARE_BRACKET => {
+                            self.state = State::OperatingSystemCommand(self.position)
+                        }
                         _ => {
                             self.state = State::Literal;
                             return Some(Sequence::Literal(*byte));
@@ -142,6 +258,17 @@ impl Iterator for AnsiParser {
                                 .unwrap_or(1);
                             return Some(Sequence::CursorForward(amount));
                         }
+                        ascii::UPPERCASE_D
+                            if self.bytes[start..self.position - 1].last()
+                                == Some(&ascii::SPACE) =>
+                        {
+                            self.state = State::Literal;
+                            let mut seq = self.bytes[start..self.position - 2]
+                                .into_sequence_iter_with_default(0);
+                            let slot = seq.next().unwrap_or(0);
+                            let font = seq.next().unwrap_or(0);
+                            return Some(Sequence::FontSelection { slot, font });
+                        }
                         ascii::UPPERCASE_D => {
                             self.state = State::Literal;
                             let amount = self.bytes[start..self.position - 1]
@@ -191,6 +318,23 @@ impl Iterator for AnsiParser {
                         ascii::UPPERCASE_M => {
                             self.state = State::Music(self.position - 1);
                         }
+                        ascii::LOWERCASE_C => {
+                            self.state = State::Literal;
+                            let bytes = &self.bytes[start..self.position - 1];
+                            // Only the primary request, "ESC[c" or "ESC[0c", is answered.
+                            if bytes.iter().all(u8::is_ascii_digit)
+                                && bytes.into_sequence_iter().next().unwrap_or(0) == 0
+                            {
+                                return Some(Sequence::DeviceAttributes);
+                            }
+                        }
+                        ascii::LOWERCASE_N => {
+                            self.state = State::Literal;
+                            let mut seq = self.bytes[start..self.position - 1].into_sequence_iter();
+                            if let Some(value) = seq.next() {
+                                return Some(Sequence::DeviceStatusReport(value));
+                            }
+                        }
                         ascii::LOWERCASE_M => {
                             self.state = State::Literal;
                             let vec = self.bytes[start..self.position - 1]
@@ -198,6 +342,17 @@ impl Iterator for AnsiParser {
                                 .collect();
                             return Some(Sequence::SelectGraphicsRendition(vec));
                         }
+                        ascii::LOWERCASE_Q
+                            if self.bytes[start..self.position - 1].last()
+                                == Some(&ascii::SPACE) =>
+                        {
+                            self.state = State::Literal;
+                            let value = self.bytes[start..self.position - 2]
+                                .into_sequence_iter_with_default(0)
+                                .next()
+                                .unwrap_or(0);
+                            return Some(Sequence::SelectCursorStyle(value));
+                        }
                         ascii::LOWERCASE_S => {
                             self.state = State::Literal;
                             return Some(Sequence::SavePosition);
@@ -273,7 +428,7 @@ impl Iterator for AnsiParser {
                         eof_start,
                         comments_start,
                     } => {
-                        if (self.position - comments_start) % 64 == 0
+                        if (self.position - comments_start).is_multiple_of(64)
                             && self.bytes[self.position..self.position + SAUCE_HEAD.len()]
                                 == SAUCE_HEAD
                         {
@@ -283,6 +438,26 @@ impl Iterator for AnsiParser {
                             };
                         }
                     }
+                    State::OperatingSystemCommand(start) => match *byte {
+                        ascii::BELL => {
+                            self.state = State::Literal;
+                            if let Some(sequence) = parse_osc(&self.bytes[start..self.position - 1])
+                            {
+                                return Some(sequence);
+                            }
+                        }
+                        ascii::ESCAPE => self.state = State::OperatingSystemCommandEscape(start),
+                        _ => {}
+                    },
+                    State::OperatingSystemCommandEscape(start) => {
+                        self.state = State::Literal;
+                        if *byte == ascii::BACKSLASH {
+                            if let Some(sequence) = parse_osc(&self.bytes[start..self.position - 2])
+                            {
+                                return Some(sequence);
+                            }
+                        }
+                    }
                     State::Music(start) => {
                         if *byte == ascii::SHIFT_OUT {
                             self.state = State::Literal;
@@ -291,8 +466,8 @@ impl Iterator for AnsiParser {
                         }
                     }
                 }
-                if let Some(baud_rate) = self.baud_rate {
-                    if self.position % (baud_rate as f32 / 8.0 / 60.0) as usize == 0 {
+                if let Some(baud_rate) = self.throttle() {
+                    if self.position.is_multiple_of(bytes_per_frame(baud_rate)) {
                         return Some(Sequence::Update);
                     }
                 }
@@ -311,3 +486,159 @@ fn test() {
         println!("{sauce}");
     }
 }
+
+#[test]
+fn test_device_queries() {
+    let mut parser = AnsiParser::new();
+    parser.set_baud_rate(None);
+    parser.input(b"\x1b[6n\x1b[5n\x1b[c\x1b[0c\x1b[>c".to_vec());
+    let sequences = parser.collect::<Vec<Sequence>>();
+    assert_eq!(sequences.len(), 4);
+    assert!(matches!(sequences[0], Sequence::DeviceStatusReport(6)));
+    assert!(matches!(sequences[1], Sequence::DeviceStatusReport(5)));
+    assert!(matches!(sequences[2], Sequence::DeviceAttributes));
+    assert!(matches!(sequences[3], Sequence::DeviceAttributes));
+}
+
+#[test]
+fn test_cursor_style() {
+    let mut parser = AnsiParser::new();
+    parser.set_baud_rate(None);
+    parser.input(b"\x1b[2 q\x1b[ q\x1b[4q".to_vec());
+    let sequences = parser.collect::<Vec<Sequence>>();
+    assert_eq!(sequences.len(), 3);
+    assert!(matches!(sequences[0], Sequence::SelectCursorStyle(2)));
+    assert!(matches!(sequences[1], Sequence::SelectCursorStyle(0)));
+    assert!(matches!(sequences[2], Sequence::Unknown { .. }));
+}
+
+#[test]
+fn test_font_selection() {
+    let mut parser = AnsiParser::new();
+    parser.set_baud_rate(None);
+    parser.input(b"\x1b[1;38 D\x1b[ D\x1b[2D".to_vec());
+    let sequences = parser.collect::<Vec<Sequence>>();
+    assert_eq!(sequences.len(), 3);
+    assert!(matches!(
+        sequences[0],
+        Sequence::FontSelection { slot: 1, font: 38 }
+    ));
+    assert!(matches!(
+        sequences[1],
+        Sequence::FontSelection { slot: 0, font: 0 }
+    ));
+    assert!(matches!(sequences[2], Sequence::CursorBack(2)));
+}
+
+#[test]
+fn test_baud_rates() {
+    let mut parser = AnsiParser::with_baud(14400);
+    parser.increase_baud_rate();
+    assert_eq!(parser.baud_rate(), Some(19200));
+    parser.decrease_baud_rate();
+    parser.decrease_baud_rate();
+    assert_eq!(parser.baud_rate(), Some(9600));
+    parser.set_baud_rate(Some(115200));
+    parser.increase_baud_rate();
+    assert_eq!(parser.baud_rate(), None);
+    parser.decrease_baud_rate();
+    assert_eq!(parser.baud_rate(), Some(115200));
+    parser.set_baud_rate(Some(300));
+    parser.decrease_baud_rate();
+    assert_eq!(parser.baud_rate(), Some(300));
+    parser.restart();
+    assert_eq!(parser.baud_rate(), Some(14400));
+}
+
+#[test]
+fn test_skip() {
+    let updates = |parser: &mut AnsiParser| {
+        parser
+            .by_ref()
+            .filter(|sequence| matches!(sequence, Sequence::Update))
+            .count()
+    };
+    // 300 baud is a byte a frame
+    let mut parser = AnsiParser::with_baud(300);
+    parser.set_literal(true);
+    parser.input(b"ABCD".to_vec());
+    parser.skip_to_end();
+    assert_eq!(updates(&mut parser), 0);
+    parser.input(b"EFGH".to_vec());
+    assert_eq!(updates(&mut parser), 4);
+    assert_eq!(parser.baud_rate(), Some(300));
+    parser.restart();
+    assert_eq!(updates(&mut parser), 7);
+}
+
+#[test]
+fn test_palette_sequences() {
+    let mut parser = AnsiParser::new();
+    parser.set_baud_rate(None);
+    parser.input(
+        b"\x1b]4;1;rgb:ff/80/00\x07\x1b]4;2;rgb:f/ffff/0;15;#102030\x1b\\\x1b]104\x07\x1b]104;3\x07\x1b]0;title\x07A"
+            .to_vec(),
+    );
+    let sequences = parser.collect::<Vec<Sequence>>();
+    assert_eq!(sequences.len(), 5);
+    assert!(
+        matches!(&sequences[0], Sequence::SetPaletteColours(colours) if colours == &[(1, [255, 128, 0])])
+    );
+    assert!(
+        matches!(&sequences[1], Sequence::SetPaletteColours(colours) if colours == &[(2, [255, 255, 0]), (15, [16, 32, 48])])
+    );
+    assert!(matches!(&sequences[2], Sequence::ResetPalette(indexes) if indexes.is_empty()));
+    assert!(matches!(&sequences[3], Sequence::ResetPalette(indexes) if indexes == &[3]));
+    assert!(matches!(sequences[4], Sequence::Literal(b'A')));
+}
+
+#[test]
+fn test_petscii_screen_codes() {
+    assert_eq!(petscii::screen_code(b'A'), Some(0x01));
+    assert_eq!(petscii::screen_code(b'1'), Some(0x31));
+    assert_eq!(petscii::screen_code(0xc1), Some(0x41));
+    assert_eq!(petscii::screen_code(0xff), Some(0x5e));
+    assert_eq!(petscii::screen_code(petscii::CLEAR), None);
+    assert_eq!(petscii::colour(petscii::LIGHT_BLUE), Some(14));
+}
+
+#[test]
+fn test_petscii_decoder() {
+    let mut decoder = petscii::PetsciiDecoder::default();
+    decoder.input(b"\x93\x1cHI\x12A\x0d\x0e\x9dB");
+    let canvas = decoder.canvas();
+    let cell = |column, row| *canvas.get(column, row).unwrap();
+    assert_eq!(cell(0, 0).code, 0x08);
+    assert_eq!(cell(0, 0).fg, petscii::PALETTE[2]);
+    assert_eq!(cell(2, 0).code, 0x81);
+    assert_eq!(cell(39, 0).code, 0x02);
+    assert_eq!(cell(0, 0).font, petscii::Charset::Shifted as usize);
+    assert_eq!(canvas.dimensions(), (320, 200));
+}
+
+#[test]
+fn test_literal_parser() {
+    let mut parser = AnsiParser::with_baud(9600);
+    parser.set_literal(true);
+    parser.input(b"\x1b[\x0d\x1a".to_vec());
+    parser.input(vec![b'A'; 20]);
+    let sequences = parser.collect::<Vec<Sequence>>();
+    assert_eq!(sequences.len(), 25);
+    assert!(matches!(sequences[0], Sequence::Literal(0x1b)));
+    assert!(matches!(sequences[3], Sequence::Literal(0x1a)));
+    assert!(matches!(sequences[20], Sequence::Update));
+}
+
+#[test]
+fn test_atascii_decoder() {
+    let mut decoder = atascii::AtasciiDecoder::default();
+    decoder.input(b"\x7dA\xc1\x9b\x1b\x1cB\x1e\xfe");
+    let canvas = decoder.canvas();
+    let cell = |column, row| *canvas.get(column, row).unwrap();
+    assert_eq!(cell(0, 0).code, 0x41);
+    assert_eq!(cell(1, 0).code, 0xc1);
+    assert_eq!(cell(0, 1).code, 0x1c);
+    assert_eq!(cell(1, 1).code, 0x20);
+    assert_eq!(cell(0, 1).bg, atascii::BACKGROUND);
+    assert_eq!(canvas.dimensions(), (320, 192));
+}
@@ -0,0 +1,65 @@
+use crate::Sequence;
+use codepage437::ascii;
+
+// Parses a single hexadecimal component of an xterm colour, which can be
+// between one and four digits and is scaled to eight bits.
+fn parse_component(string: &str) -> Option<u8> {
+    if string.is_empty() || string.len() > 4 {
+        return None;
+    }
+    let value = u32::from_str_radix(string, 16).ok()?;
+    let max = (1 << (string.len() * 4)) - 1;
+    Some((value * 255 / max) as u8)
+}
+
+// Parses a colour as either "rgb:rr/gg/bb" or "#rrggbb".
+fn parse_colour(string: &str) -> Option<[u8; 3]> {
+    if let Some(spec) = string.strip_prefix("rgb:") {
+        let mut components = spec.split('/').map(parse_component);
+        match (
+            components.next(),
+            components.next(),
+            components.next(),
+            components.next(),
+        ) {
+            (Some(Some(r)), Some(Some(g)), Some(Some(b)), None) => Some([r, g, b]),
+            _ => None,
+        }
+    } else if let Some(hex) = string.strip_prefix('#') {
+        if hex.len() != 6 {
+            return None;
+        }
+        Some([
+            parse_component(&hex[0..2])?,
+            parse_component(&hex[2..4])?,
+            parse_component(&hex[4..6])?,
+        ])
+    } else {
+        None
+    }
+}
+
+/// Parses the body of an Operating System Command, only the xterm palette
+/// commands `ESC]4;n;rgb:rr/gg/bb` and `ESC]104;n` are recognised.
+pub(crate) fn parse_osc(bytes: &[u8]) -> Option<Sequence> {
+    let string = std::str::from_utf8(bytes).ok()?;
+    let mut params = string.split(ascii::SEMI_COLON as char);
+    match params.next()? {
+        "4" => {
+            let params: Vec<&str> = params.collect();
+            let colours: Vec<(usize, [u8; 3])> = params
+                .chunks_exact(2)
+                .filter_map(|pair| Some((pair[0].parse().ok()?, parse_colour(pair[1])?)))
+                .collect();
+            if colours.is_empty() {
+                None
+            } else {
+                Some(Sequence::SetPaletteColours(colours))
+            }
+        }
+        "104" => Some(Sequence::ResetPalette(
+            params.filter_map(|index| index.parse().ok()).collect(),
+        )),
+        _ => None,
+    }
+}
@@ -0,0 +1,191 @@
+use crate::{
+    canvas::{Canvas, CanvasCell},
+    petscii::{control, screen_code, Charset, Control, PALETTE},
+};
+use codepage437::ascii;
+
+const COLUMNS: usize = 40;
+const ROWS: usize = 25;
+// Light blue on black
+const COLOUR: usize = 14;
+const BACKGROUND: usize = 0;
+
+/// Decodes a PETSCII stream, such as a `.SEQ` file, onto a [Canvas] the size
+/// of the Commodore 64's screen, with the C64's light blue characters on black
+pub struct PetsciiDecoder {
+    canvas: Canvas,
+    column: usize,
+    // Equal to the number of rows when the next character scrolls the screen
+    row: usize,
+    colour: usize,
+    background: usize,
+    reverse: bool,
+    charset: Charset,
+}
+
+impl Default for PetsciiDecoder {
+    fn default() -> Self {
+        PetsciiDecoder::new(ROWS)
+    }
+}
+
+impl PetsciiDecoder {
+    /// Constructs a decoder for a 40 column screen of `rows`
+    pub fn new(rows: usize) -> PetsciiDecoder {
+        let fonts = vec![Charset::Unshifted.font(), Charset::Shifted.font()];
+        let blank = CanvasCell {
+            code: ascii::SPACE,
+            fg: PALETTE[COLOUR],
+            bg: PALETTE[BACKGROUND],
+            font: Charset::Unshifted as usize,
+        };
+        PetsciiDecoder {
+            canvas: Canvas::new(COLUMNS, rows.max(1), fonts, blank),
+            column: 0,
+            row: 0,
+            colour: COLOUR,
+            background: BACKGROUND,
+            reverse: false,
+            charset: Charset::Unshifted,
+        }
+    }
+
+    pub fn canvas(&self) -> &Canvas {
+        &self.canvas
+    }
+
+    /// Decodes `bytes`, stopping at an end of file character so that a
+    /// SAUCE record isn't drawn
+    pub fn input(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            if *byte == ascii::END_OF_FILE {
+                break;
+            }
+            match control(*byte) {
+                Some(control) => self.control(control),
+                None => {
+                    if let Some(code) = screen_code(*byte) {
+                        // The reversed characters are in the second half of the font
+                        self.draw(if self.reverse { code | 0x80 } else { code });
+                    }
+                }
+            }
+        }
+    }
+
+    fn blank(&self) -> CanvasCell {
+        CanvasCell {
+            code: ascii::SPACE,
+            fg: PALETTE[self.colour],
+            bg: PALETTE[self.background],
+            font: self.charset as usize,
+        }
+    }
+
+    fn control(&mut self, control: Control) {
+        match control {
+            Control::Colour(colour) => self.colour = colour,
+            Control::Return => {
+                self.reverse = false;
+                self.column = 0;
+                self.cursor_down();
+            }
+            Control::ReverseOn => self.reverse = true,
+            Control::ReverseOff => self.reverse = false,
+            Control::Clear => {
+                self.canvas.clear(self.blank());
+                (self.column, self.row) = (0, 0);
+            }
+            Control::Home => (self.column, self.row) = (0, 0),
+            Control::CursorUp => self.row = self.row.min(self.canvas.rows - 1).saturating_sub(1),
+            Control::CursorDown => self.cursor_down(),
+            Control::CursorLeft => self.cursor_left(),
+            Control::CursorRight => {
+                if self.column + 1 < self.canvas.columns {
+                    self.column += 1;
+                } else {
+                    self.column = 0;
+                    self.cursor_down();
+                }
+            }
+            Control::Delete => {
+                self.cursor_left();
+                self.shift_line(false);
+            }
+            Control::Insert => self.shift_line(true),
+            Control::Charset(charset) => {
+                // The character set applies to the whole screen at once
+                self.charset = charset;
+                for cell in self.canvas.cells_mut() {
+                    cell.font = charset as usize;
+                }
+            }
+        }
+    }
+
+    fn cursor_down(&mut self) {
+        if self.row + 1 < self.canvas.rows {
+            self.row += 1;
+        } else {
+            self.canvas.scroll_up(self.blank());
+            self.row = self.canvas.rows - 1;
+        }
+    }
+
+    fn cursor_left(&mut self) {
+        if self.column > 0 {
+            self.column -= 1;
+        } else if self.row > 0 {
+            self.column = self.canvas.columns - 1;
+            self.row = self.row.min(self.canvas.rows) - 1;
+        }
+    }
+
+    // Moves the rest of the line after the cursor right to insert a space, or
+    // left to delete the character at the cursor
+    fn shift_line(&mut self, insert: bool) {
+        if self.row >= self.canvas.rows {
+            return;
+        }
+        let blank = self.blank();
+        let mut line: Vec<CanvasCell> = (self.column..self.canvas.columns)
+            .filter_map(|column| self.canvas.get(column, self.row).copied())
+            .collect();
+        if insert {
+            line.pop();
+            line.insert(0, blank);
+        } else {
+            line.remove(0);
+            line.push(blank);
+        }
+        for (column, cell) in (self.column..).zip(line) {
+            if let Some(existing) = self.canvas.get_mut(column, self.row) {
+                *existing = cell;
+            }
+        }
+    }
+
+    fn draw(&mut self, code: u8) {
+        if self.row == self.canvas.rows {
+            self.canvas.scroll_up(self.blank());
+            self.row -= 1;
+        }
+        let cell = CanvasCell {
+            code,
+            fg: PALETTE[self.colour],
+            bg: PALETTE[self.background],
+            font: self.charset as usize,
+        };
+        if let Some(existing) = self.canvas.get_mut(self.column, self.row) {
+            *existing = cell;
+        }
+        if self.column + 1 < self.canvas.columns {
+            self.column += 1;
+        } else {
+            // Scrolling waits for the next character, so that the last line
+            // can be filled without moving the screen
+            self.column = 0;
+            self.row += 1;
+        }
+    }
+}
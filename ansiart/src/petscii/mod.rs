@@ -0,0 +1,162 @@
+//! Control codes and conversions for the Commodore 64's character set
+
+mod decoder;
+use codepage437::{raw, Font};
+pub use decoder::PetsciiDecoder;
+use ega_palette::Rgb;
+
+pub const WHITE: u8 = 0x05;
+pub const DISABLE_CASE_SWITCH: u8 = 0x08;
+pub const ENABLE_CASE_SWITCH: u8 = 0x09;
+pub const RETURN: u8 = 0x0d;
+pub const LOWERCASE: u8 = 0x0e;
+pub const CURSOR_DOWN: u8 = 0x11;
+pub const REVERSE_ON: u8 = 0x12;
+pub const HOME: u8 = 0x13;
+pub const DELETE: u8 = 0x14;
+pub const RED: u8 = 0x1c;
+pub const CURSOR_RIGHT: u8 = 0x1d;
+pub const GREEN: u8 = 0x1e;
+pub const BLUE: u8 = 0x1f;
+pub const ORANGE: u8 = 0x81;
+pub const SHIFTED_RETURN: u8 = 0x8d;
+pub const UPPERCASE: u8 = 0x8e;
+pub const BLACK: u8 = 0x90;
+pub const CURSOR_UP: u8 = 0x91;
+pub const REVERSE_OFF: u8 = 0x92;
+pub const CLEAR: u8 = 0x93;
+pub const INSERT: u8 = 0x94;
+pub const BROWN: u8 = 0x95;
+pub const LIGHT_RED: u8 = 0x96;
+pub const DARK_GREY: u8 = 0x97;
+pub const GREY: u8 = 0x98;
+pub const LIGHT_GREEN: u8 = 0x99;
+pub const LIGHT_BLUE: u8 = 0x9a;
+pub const LIGHT_GREY: u8 = 0x9b;
+pub const PURPLE: u8 = 0x9c;
+pub const CURSOR_LEFT: u8 = 0x9d;
+pub const YELLOW: u8 = 0x9e;
+pub const CYAN: u8 = 0x9f;
+
+/// The Commodore 64's colours as measured by Pepto, in the C64's own order
+pub const PALETTE: [Rgb; 16] = [
+    [0x00, 0x00, 0x00],
+    [0xff, 0xff, 0xff],
+    [0x68, 0x37, 0x2b],
+    [0x70, 0xa4, 0xb2],
+    [0x6f, 0x3d, 0x86],
+    [0x58, 0x8d, 0x43],
+    [0x35, 0x28, 0x79],
+    [0xb8, 0xc7, 0x6f],
+    [0x6f, 0x4f, 0x25],
+    [0x43, 0x39, 0x00],
+    [0x9a, 0x67, 0x59],
+    [0x44, 0x44, 0x44],
+    [0x6c, 0x6c, 0x6c],
+    [0x9a, 0xd2, 0x84],
+    [0x6c, 0x5e, 0xb5],
+    [0x95, 0x95, 0x95],
+];
+
+/// The C64's two character sets, the unshifted set has uppercase letters and
+/// graphics, the shifted set has lowercase and uppercase letters
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Charset {
+    #[default]
+    Unshifted,
+    Shifted,
+}
+
+impl Charset {
+    pub fn font(&self) -> Font {
+        let bytes: &[u8] = match self {
+            Charset::Unshifted => raw::PETSCII_UNSHIFTED_F08,
+            Charset::Shifted => raw::PETSCII_SHIFTED_F08,
+        };
+        Font::try_from(bytes).expect("font")
+    }
+}
+
+/// What a PETSCII control code does
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Control {
+    Colour(usize),
+    /// Moves to the start of the next line and turns off reverse video
+    Return,
+    ReverseOn,
+    ReverseOff,
+    Clear,
+    Home,
+    CursorUp,
+    CursorDown,
+    CursorLeft,
+    CursorRight,
+    Delete,
+    Insert,
+    Charset(Charset),
+}
+
+/// Returns what a control code does, or `None` for printable characters and
+/// codes which have no effect on the screen
+pub fn control(byte: u8) -> Option<Control> {
+    if let Some(colour) = colour(byte) {
+        return Some(Control::Colour(colour));
+    }
+    let control = match byte {
+        RETURN | SHIFTED_RETURN => Control::Return,
+        REVERSE_ON => Control::ReverseOn,
+        REVERSE_OFF => Control::ReverseOff,
+        CLEAR => Control::Clear,
+        HOME => Control::Home,
+        CURSOR_UP => Control::CursorUp,
+        CURSOR_DOWN => Control::CursorDown,
+        CURSOR_LEFT => Control::CursorLeft,
+        CURSOR_RIGHT => Control::CursorRight,
+        DELETE => Control::Delete,
+        INSERT => Control::Insert,
+        LOWERCASE => Control::Charset(Charset::Shifted),
+        UPPERCASE => Control::Charset(Charset::Unshifted),
+        _ => return None,
+    };
+    Some(control)
+}
+
+/// Returns the C64 colour, from 0 to 15, selected by a colour control code
+pub fn colour(byte: u8) -> Option<usize> {
+    let colour = match byte {
+        BLACK => 0,
+        WHITE => 1,
+        RED => 2,
+        CYAN => 3,
+        PURPLE => 4,
+        GREEN => 5,
+        BLUE => 6,
+        YELLOW => 7,
+        ORANGE => 8,
+        BROWN => 9,
+        LIGHT_RED => 10,
+        DARK_GREY => 11,
+        GREY => 12,
+        LIGHT_GREEN => 13,
+        LIGHT_BLUE => 14,
+        LIGHT_GREY => 15,
+        _ => return None,
+    };
+    Some(colour)
+}
+
+/// Converts a printable PETSCII character to the screen code used to index
+/// the C64's fonts, returns `None` for control codes
+pub fn screen_code(byte: u8) -> Option<u8> {
+    let code = match byte {
+        0x20..=0x3f => byte,
+        0x40..=0x5f => byte - 0x40,
+        0x60..=0x7f => byte - 0x20,
+        0xa0..=0xbf => byte - 0x40,
+        0xc0..=0xdf => byte - 0x80,
+        0xe0..=0xfe => byte - 0x80,
+        0xff => 0x5e,
+        _ => return None,
+    };
+    Some(code)
+}
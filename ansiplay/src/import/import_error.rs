@@ -0,0 +1,26 @@
+use std::{error, fmt};
+
+/// Custom error used when importing a [crate::Melody]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImportError {
+    InvalidMidi,
+    UnsupportedMidi,
+    /// The position of the first character that couldn't be parsed
+    InvalidMml(usize),
+    NoNotes,
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::InvalidMidi => write!(f, "Invalid MIDI file"),
+            ImportError::UnsupportedMidi => write!(f, "Unsupported MIDI time division"),
+            ImportError::InvalidMml(position) => {
+                write!(f, "Invalid MML at character {position}")
+            }
+            ImportError::NoNotes => write!(f, "No notes found"),
+        }
+    }
+}
+
+impl error::Error for ImportError {}
@@ -0,0 +1,274 @@
+use crate::import::{snap, Event, ImportError, Melody, WHOLE};
+
+// Channel 10, counting from zero
+const PERCUSSION: u8 = 9;
+const DEFAULT_TEMPO: usize = 120;
+
+#[derive(Clone, Copy, Debug)]
+enum MidiEvent {
+    // Ordered so that a note released on the same tick as another starts is
+    // released first
+    Off(u8),
+    Tempo(usize),
+    On(u8),
+}
+
+impl MidiEvent {
+    fn order(&self) -> u8 {
+        match self {
+            MidiEvent::Off(_) => 0,
+            MidiEvent::Tempo(_) => 1,
+            MidiEvent::On(_) => 2,
+        }
+    }
+}
+
+// A note of the highest voice, with its times in ticks
+struct Segment {
+    key: u8,
+    start: u64,
+    end: u64,
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Reader<'a> {
+        Reader { bytes, position: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.position >= self.bytes.len()
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ImportError> {
+        let bytes = self
+            .bytes
+            .get(self.position..self.position + len)
+            .ok_or(ImportError::InvalidMidi)?;
+        self.position += len;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8, ImportError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn peek(&self) -> Result<u8, ImportError> {
+        self.bytes
+            .get(self.position)
+            .copied()
+            .ok_or(ImportError::InvalidMidi)
+    }
+
+    fn u16(&mut self) -> Result<u16, ImportError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, ImportError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    // Reads a variable length quantity, seven bits at a time
+    fn variable(&mut self) -> Result<u64, ImportError> {
+        let mut value = 0;
+        for _ in 0..4 {
+            let byte = self.byte()?;
+            value = (value << 7) | (byte & 0x7f) as u64;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(ImportError::InvalidMidi)
+    }
+}
+
+pub(crate) fn read(bytes: &[u8]) -> Result<Melody, ImportError> {
+    let mut reader = Reader::new(bytes);
+    if reader.take(4)? != b"MThd" {
+        return Err(ImportError::InvalidMidi);
+    }
+    let len = reader.u32()? as usize;
+    let mut header = Reader::new(reader.take(len)?);
+    let _format = header.u16()?;
+    let tracks = header.u16()?;
+    let division = header.u16()?;
+    // Divisions in SMPTE frames aren't musical time
+    if division & 0x8000 != 0 || division == 0 {
+        return Err(ImportError::UnsupportedMidi);
+    }
+    let mut events = Vec::new();
+    let mut read_tracks = 0;
+    while read_tracks < tracks && !reader.is_empty() {
+        let id = reader.take(4)?;
+        let len = reader.u32()? as usize;
+        let chunk = reader.take(len)?;
+        // Unknown chunks are skipped
+        if id == b"MTrk" {
+            read_track(chunk, &mut events)?;
+            read_tracks += 1;
+        }
+    }
+    events.sort_by_key(|(tick, event)| (*tick, event.order()));
+    let melody = melody(&highest_voice(&events), &events, division as u64);
+    if melody.has_notes() {
+        Ok(melody)
+    } else {
+        Err(ImportError::NoNotes)
+    }
+}
+
+fn read_track(bytes: &[u8], events: &mut Vec<(u64, MidiEvent)>) -> Result<(), ImportError> {
+    let mut reader = Reader::new(bytes);
+    let mut tick = 0;
+    let mut running_status = None;
+    while !reader.is_empty() {
+        tick += reader.variable()?;
+        let status = match reader.peek()? {
+            byte if byte & 0x80 != 0 => reader.byte()?,
+            _ => running_status.ok_or(ImportError::InvalidMidi)?,
+        };
+        match status {
+            0xff => {
+                let kind = reader.byte()?;
+                let len = reader.variable()? as usize;
+                let data = reader.take(len)?;
+                match kind {
+                    0x2f => break,
+                    0x51 if len == 3 => {
+                        let micros = u32::from_be_bytes([0, data[0], data[1], data[2]]);
+                        let tempo = (60_000_000.0 / micros.max(1) as f64).round() as usize;
+                        events.push((tick, MidiEvent::Tempo(tempo)));
+                    }
+                    _ => {}
+                }
+            }
+            0xf0 | 0xf7 => {
+                let len = reader.variable()? as usize;
+                reader.take(len)?;
+            }
+            0x80..=0xef => {
+                running_status = Some(status);
+                let channel = status & 0x0f;
+                match status & 0xf0 {
+                    0x80 | 0x90 => {
+                        let key = reader.byte()?;
+                        let velocity = reader.byte()?;
+                        if channel != PERCUSSION {
+                            let event = match (status & 0xf0, velocity) {
+                                (0x90, velocity) if velocity > 0 => MidiEvent::On(key),
+                                _ => MidiEvent::Off(key),
+                            };
+                            events.push((tick, event));
+                        }
+                    }
+                    0xc0 | 0xd0 => {
+                        reader.byte()?;
+                    }
+                    _ => {
+                        reader.take(2)?;
+                    }
+                }
+            }
+            _ => return Err(ImportError::InvalidMidi),
+        }
+    }
+    Ok(())
+}
+
+// Follows the highest key held down, a new note starts whenever the highest
+// key changes or is struck again
+fn highest_voice(events: &[(u64, MidiEvent)]) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut held: Vec<u8> = Vec::new();
+    let mut current: Option<Segment> = None;
+    let mut index = 0;
+    while let Some(&(tick, _)) = events.get(index) {
+        let mut struck = Vec::new();
+        while let Some(&(_, event)) = events.get(index).filter(|event| event.0 == tick) {
+            match event {
+                MidiEvent::Off(key) => {
+                    if let Some(position) = held.iter().position(|held| *held == key) {
+                        held.remove(position);
+                    }
+                }
+                MidiEvent::On(key) => {
+                    held.push(key);
+                    struck.push(key);
+                }
+                MidiEvent::Tempo(_) => {}
+            }
+            index += 1;
+        }
+        let top = held.iter().max().copied();
+        let changed = top != current.as_ref().map(|segment| segment.key)
+            || top.is_some_and(|top| struck.contains(&top));
+        if changed {
+            if let Some(mut segment) = current.take() {
+                segment.end = tick;
+                segments.push(segment);
+            }
+            current = top.map(|key| Segment {
+                key,
+                start: tick,
+                end: tick,
+            });
+        }
+    }
+    if let Some(mut segment) = current {
+        segment.end = events.last().map_or(segment.start, |event| event.0);
+        segments.push(segment);
+    }
+    segments
+}
+
+fn melody(segments: &[Segment], events: &[(u64, MidiEvent)], division: u64) -> Melody {
+    // Converts ticks to units, rounding to the nearest unit
+    let quarter = (WHOLE / 4) as u64;
+    let units = |tick: u64| ((tick * quarter * 2 + division) / (division * 2)) as usize;
+    let mut tempos = events
+        .iter()
+        .filter_map(|(tick, event)| match event {
+            MidiEvent::Tempo(tempo) => Some((*tick, *tempo)),
+            _ => None,
+        })
+        .peekable();
+    let mut melody = Melody::default();
+    melody.push(Event::Tempo(DEFAULT_TEMPO));
+    let mut cursor = 0;
+    for (index, segment) in segments.iter().enumerate() {
+        while let Some((_, tempo)) = tempos.next_if(|(tick, _)| *tick <= segment.start) {
+            melody.push(Event::Tempo(tempo));
+        }
+        let start = units(segment.start);
+        if start > cursor {
+            melody.push(Event::Rest(start - cursor));
+            cursor = start;
+        }
+        let sounding = units(segment.end).saturating_sub(start);
+        // A note that sounds for most of the time until the next note is
+        // joined to it, otherwise it's either been played for 7/8ths of its
+        // length, as ANSI Music does, or for all of it, and is followed by a rest
+        let until_next = segments
+            .get(index + 1)
+            .map(|next| units(next.start) - start);
+        let length = match until_next {
+            Some(until_next) if sounding * 4 >= until_next * 3 => {
+                snap(until_next).unwrap_or(until_next)
+            }
+            _ => snap((sounding * 8 + 3) / 7)
+                .or_else(|| snap(sounding))
+                .unwrap_or(sounding),
+        };
+        if length > 0 {
+            melody.push(Event::Note(segment.key, length));
+            cursor += length;
+        }
+    }
+    melody
+}
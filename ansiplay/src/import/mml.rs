@@ -0,0 +1,205 @@
+use crate::import::{Event, ImportError, Melody, WHOLE};
+
+const DEFAULT_OCTAVE: i32 = 4;
+const DEFAULT_LENGTH: usize = WHOLE / 4;
+const DEFAULT_TEMPO: usize = 120;
+const DEFAULT_REPEATS: usize = 2;
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+    octave: i32,
+    length: usize,
+    // The position after each open loop, and the repeats remaining once its
+    // end has been reached
+    loops: Vec<(usize, Option<usize>)>,
+    melody: Melody,
+}
+
+pub(crate) fn read(mml: &str) -> Result<Melody, ImportError> {
+    // Channels are separated by semicolons
+    let channel = mml.split(';').next().unwrap_or_default();
+    let mut parser = Parser {
+        chars: channel.chars().collect(),
+        position: 0,
+        octave: DEFAULT_OCTAVE,
+        length: DEFAULT_LENGTH,
+        loops: Vec::new(),
+        melody: Melody::default(),
+    };
+    parser.melody.push(Event::Tempo(DEFAULT_TEMPO));
+    parser.parse()?;
+    if parser.melody.has_notes() {
+        Ok(parser.melody)
+    } else {
+        Err(ImportError::NoNotes)
+    }
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars
+            .get(self.position)
+            .map(|char| char.to_ascii_lowercase())
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|char| char.is_whitespace()) {
+            self.position += 1;
+        }
+    }
+
+    fn int(&mut self) -> Option<usize> {
+        let start = self.position;
+        while self.peek().is_some_and(|char| char.is_ascii_digit()) {
+            self.position += 1;
+        }
+        self.chars[start..self.position]
+            .iter()
+            .collect::<String>()
+            .parse()
+            .ok()
+    }
+
+    // Parses an optional length followed by any dots, returned in units
+    fn length(&mut self, default: usize) -> Result<usize, ImportError> {
+        let position = self.position;
+        let mut units = match self.int() {
+            Some(0) => return Err(ImportError::InvalidMml(position)),
+            Some(length) => (WHOLE + length / 2) / length,
+            None => default,
+        };
+        let mut dot = units;
+        while self.peek() == Some('.') {
+            self.position += 1;
+            dot /= 2;
+            units += dot;
+        }
+        Ok(units)
+    }
+
+    // Parses a note after its letter, returning its key and length
+    fn note(&mut self, semitone: i32) -> Result<(u8, usize), ImportError> {
+        let mut semitone = semitone;
+        loop {
+            match self.peek() {
+                Some('+' | '#') => semitone += 1,
+                Some('-') => semitone -= 1,
+                _ => break,
+            }
+            self.position += 1;
+        }
+        let key = ((self.octave + 1) * 12 + semitone).clamp(0, 127) as u8;
+        Ok((key, self.length(self.length)?))
+    }
+
+    // Extends a note with any ties, either "^" and a length, or "&" and a
+    // note of the same pitch
+    fn ties(&mut self, key: u8, mut units: usize) -> Result<usize, ImportError> {
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('^') => {
+                    self.position += 1;
+                    units += self.length(self.length)?;
+                }
+                Some('&') => {
+                    self.position += 1;
+                    self.skip_whitespace();
+                    let position = self.position;
+                    match self.peek().and_then(semitone) {
+                        Some(semitone) => {
+                            self.position += 1;
+                            match self.note(semitone)? {
+                                (tied, length) if tied == key => units += length,
+                                // A slur to another note, which is played as usual
+                                _ => {
+                                    self.position = position;
+                                    return Ok(units);
+                                }
+                            }
+                        }
+                        None => return Ok(units),
+                    }
+                }
+                _ => return Ok(units),
+            }
+        }
+    }
+
+    fn parse(&mut self) -> Result<(), ImportError> {
+        while let Some(char) = self.peek() {
+            let position = self.position;
+            self.position += 1;
+            match char {
+                'a'..='g' => {
+                    let (key, units) = self.note(semitone(char).unwrap_or_default())?;
+                    let units = self.ties(key, units)?;
+                    self.melody.push(Event::Note(key, units));
+                }
+                'n' => {
+                    let key = self.int().ok_or(ImportError::InvalidMml(position))?;
+                    let units = self.length(self.length)?;
+                    self.melody.push(Event::Note(key.min(127) as u8, units));
+                }
+                'r' | 'p' => {
+                    let units = self.length(self.length)?;
+                    self.melody.push(Event::Rest(units));
+                }
+                'o' => {
+                    let octave = self.int().ok_or(ImportError::InvalidMml(position))?;
+                    self.octave = octave as i32;
+                }
+                '>' => self.octave += 1,
+                '<' => self.octave -= 1,
+                'l' => self.length = self.length(DEFAULT_LENGTH)?,
+                't' => {
+                    let tempo = self.int().ok_or(ImportError::InvalidMml(position))?;
+                    // Any fraction of a tempo is ignored
+                    if self.peek() == Some('.') {
+                        self.position += 1;
+                        self.int();
+                    }
+                    self.melody.push(Event::Tempo(tempo));
+                }
+                // Volume, gate time, and instruments have no equivalent
+                'v' | 'q' | '@' => {
+                    self.int();
+                }
+                '[' => self.loops.push((self.position, None)),
+                ']' => {
+                    let repeats = self.int().unwrap_or(DEFAULT_REPEATS);
+                    match self.loops.last_mut() {
+                        Some((start, remaining)) => {
+                            let remaining = remaining.get_or_insert(repeats.saturating_sub(1));
+                            if *remaining > 0 {
+                                *remaining -= 1;
+                                self.position = *start;
+                            } else {
+                                self.loops.pop();
+                            }
+                        }
+                        None => return Err(ImportError::InvalidMml(position)),
+                    }
+                }
+                '|' => {}
+                char if char.is_whitespace() => {}
+                _ => return Err(ImportError::InvalidMml(position)),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn semitone(char: char) -> Option<i32> {
+    match char {
+        'c' => Some(0),
+        'd' => Some(2),
+        'e' => Some(4),
+        'f' => Some(5),
+        'g' => Some(7),
+        'a' => Some(9),
+        'b' => Some(11),
+        _ => None,
+    }
+}
@@ -0,0 +1,158 @@
+mod import_error;
+mod midi;
+mod mml;
+use crate::music::{Music, MAX_OCTAVE, TEMPOS};
+use codepage437::ascii;
+pub use import_error::ImportError;
+use std::fmt::{self, Display, Formatter};
+
+// The length of a whole note, in units small enough to describe dotted notes
+// and triplets down to a sixty-fourth note
+pub(crate) const WHOLE: usize = 192;
+// The key of the player's lowest note, the C two octaves below middle C
+const LOWEST_KEY: usize = 36;
+const NAMES: [&str; 12] = [
+    "C", "C+", "D", "D+", "E", "F", "F+", "G", "G+", "A", "A+", "B",
+];
+const SHORTEST: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Event {
+    /// Quarter notes a minute
+    Tempo(usize),
+    /// A MIDI key, and its length in units of [WHOLE]
+    Note(u8, usize),
+    Rest(usize),
+}
+
+/// A monophonic tune imported from a MIDI file or MML, which can be written
+/// as ANSI Music in the syntax of GW-BASIC's `PLAY` statement.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Melody {
+    events: Vec<Event>,
+}
+
+impl Melody {
+    /// Reads a Standard MIDI File, merging every track and taking the
+    /// highest note wherever there is a chord, percussion is ignored
+    pub fn from_midi(bytes: &[u8]) -> Result<Melody, ImportError> {
+        midi::read(bytes)
+    }
+
+    /// Parses Music Macro Language, as written for MML compilers and
+    /// trackers, only the first channel is read if there are several
+    pub fn from_mml(mml: &str) -> Result<Melody, ImportError> {
+        mml::read(mml)
+    }
+
+    pub fn to_music(&self) -> Music {
+        Music::from(self.to_string().as_str())
+    }
+
+    /// Returns the melody as an ANSI Music sequence, ready to be embedded in
+    /// an ANSI file
+    pub fn to_sequence(&self) -> Vec<u8> {
+        let mut bytes = vec![ascii::ESCAPE, ascii::LEFT_SQUARE_BRACKET];
+        bytes.extend_from_slice(b"MF");
+        bytes.extend_from_slice(self.to_string().as_bytes());
+        bytes.push(ascii::SHIFT_OUT);
+        bytes
+    }
+
+    // Adds an event, merging it with the previous event if they're both rests,
+    // or both tempos
+    pub(crate) fn push(&mut self, event: Event) {
+        match (self.events.last_mut(), event) {
+            (Some(Event::Tempo(last)), Event::Tempo(tempo)) => *last = tempo,
+            (Some(Event::Rest(last)), Event::Rest(units)) => *last += units,
+            (_, Event::Rest(0)) => {}
+            _ => self.events.push(event),
+        }
+    }
+
+    pub(crate) fn has_notes(&self) -> bool {
+        self.events
+            .iter()
+            .any(|event| matches!(event, Event::Note(..)))
+    }
+}
+
+impl Display for Melody {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut octave = None;
+        for event in &self.events {
+            match *event {
+                Event::Tempo(tempo) => {
+                    write!(f, "T{}", tempo.clamp(*TEMPOS.start(), *TEMPOS.end()))?
+                }
+                Event::Note(key, units) => {
+                    // Notes out of the player's range are moved by octaves
+                    let mut index = key as usize;
+                    while index < LOWEST_KEY {
+                        index += 12;
+                    }
+                    index -= LOWEST_KEY;
+                    while index > MAX_OCTAVE * 12 + 11 {
+                        index -= 12;
+                    }
+                    for (length, dotted) in lengths(units, true) {
+                        if octave != Some(index / 12) {
+                            octave = Some(index / 12);
+                            write!(f, "O{}", index / 12)?;
+                        }
+                        let dot = if dotted { "." } else { "" };
+                        write!(f, "{}{length}{dot}", NAMES[index % 12])?;
+                    }
+                }
+                Event::Rest(units) => {
+                    for (length, _) in lengths(units, false) {
+                        write!(f, "P{length}")?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// The note lengths that are a whole number of units, as the length's number
+// and whether it's dotted, from the longest to the shortest
+fn pieces(dotted: bool, triplets: bool) -> Vec<(usize, usize, bool)> {
+    let mut pieces = Vec::new();
+    for length in 1..=SHORTEST {
+        if !triplets && !length.is_power_of_two() {
+            continue;
+        }
+        if WHOLE.is_multiple_of(length) {
+            pieces.push((WHOLE / length, length, false));
+        }
+        if dotted && (WHOLE * 3).is_multiple_of(length * 2) {
+            pieces.push((WHOLE * 3 / (length * 2), length, true));
+        }
+    }
+    pieces.sort_by(|a, b| b.0.cmp(&a.0).then(a.2.cmp(&b.2)));
+    pieces
+}
+
+// Divides a number of units into notes, preferring lengths without triplets,
+// anything shorter than the shortest note is dropped
+fn lengths(units: usize, dotted: bool) -> Vec<(usize, bool)> {
+    let pieces = pieces(dotted, !units.is_multiple_of(WHOLE / SHORTEST));
+    let mut remaining = units;
+    let mut lengths = Vec::new();
+    while let Some(&(value, length, dotted)) = pieces.iter().find(|piece| piece.0 <= remaining) {
+        lengths.push((length, dotted));
+        remaining -= value;
+    }
+    lengths
+}
+
+// Returns the nearest length of a single note to a number of units, if it's
+// within an eighth of it, to correct for rounding
+pub(crate) fn snap(units: usize) -> Option<usize> {
+    pieces(true, true)
+        .into_iter()
+        .map(|(value, _, _)| value)
+        .min_by_key(|value| value.abs_diff(units))
+        .filter(|value| value.abs_diff(units) * 8 <= *value)
+}
@@ -1,22 +1,35 @@
+mod import;
+mod mixer;
 mod music;
+mod music_error;
 mod music_sequence_iterator;
 mod player;
+mod renderer;
 pub use basic_waves::rodio;
+pub use import::{ImportError, Melody};
+pub use mixer::{Mixer, MixerError};
 pub use music::Music;
+pub use music_error::{MusicError, MusicErrorKind};
 pub use music_sequence_iterator::{IntoMusicSequenceIter, MusicSequenceIterator};
-pub use player::{Player, PlayerThread};
+pub use player::{AudioOutput, PcSpeaker, Player, PlayerThread, Progress, Voice, Waveform};
+pub use renderer::{MidiRenderer, Renderer, RendererError};
 
 #[cfg(test)]
 mod test {
-    use crate::{Music, Player};
-    use basic_waves::rodio::{OutputStream, Sink};
+    use crate::{
+        player::Tone, AudioOutput, Melody, MidiRenderer, Mixer, MixerError, Music, PcSpeaker,
+        Player, PlayerThread, Progress, Renderer, Voice, Waveform,
+    };
+    use std::{
+        thread,
+        time::{Duration, Instant},
+    };
 
+    // Renders rather than playing, so that the tests don't need a sound card
     fn play_str(string: &str) {
-        let (_stream, stream_handle) = OutputStream::try_default().unwrap();
-        let mut player = Player::default();
-        let music = Music::from(string);
-        let sink = Sink::try_new(&stream_handle).expect("sink");
-        player.play(music, &sink);
+        let mut renderer = Renderer::new(8000);
+        renderer.render(Music::from(string));
+        assert!(!renderer.samples().is_empty());
     }
 
     #[test]
@@ -48,4 +61,266 @@ mod test {
     fn tutor_variation() {
         play_str("100;2;10;5;*");
     }
+
+    #[test]
+    fn null_output_keeps_time() {
+        let output = AudioOutput::null();
+        let sink = output.sink().unwrap();
+        let mut player = Player::default();
+        let start = Instant::now();
+        player.play(Music::from("T120 L4 C D"), &sink);
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(900), "{elapsed:?}");
+        assert!(elapsed < Duration::from_millis(1500), "{elapsed:?}");
+    }
+
+    #[test]
+    fn render_timing() {
+        let mut renderer = Renderer::new(48000);
+        // A quarter note at 120 beats a minute plays for 7/8ths of 500ms
+        renderer.render(Music::from("T120 L4 C P4"));
+        let samples = renderer.samples();
+        assert_eq!(samples.len(), 48 * (438 + 63 + 500));
+        assert!(samples[..48 * 438].iter().all(|sample| sample.abs() <= 0.1));
+        assert!(samples[..48 * 438].iter().any(|sample| *sample != 0.0));
+        assert!(samples[48 * 438..].iter().all(|sample| *sample == 0.0));
+        let mut again = Renderer::new(48000);
+        again.render(Music::from("T120 L4 C P4"));
+        assert_eq!(samples, again.samples());
+    }
+
+    #[test]
+    fn render_sound_codes() {
+        let mut renderer = Renderer::new(8000);
+        // Two cycles of 1 tick each, a tick being 1/18.2 of a second,
+        // followed by a delay of 100ms
+        renderer.render(Music::from("440;1;2;100"));
+        assert_eq!(renderer.samples().len(), 1679);
+        renderer.render(Music::from(";;;250"));
+        assert_eq!(renderer.duration().as_millis(), 459);
+        // Short cycles aren't rounded to the millisecond, so they don't drift
+        let mut renderer = Renderer::new(48000);
+        renderer.render(Music::from("7000;.12;200;25;-100"));
+        let expected = (200.0 * 0.12 / 18.2 + 0.025) * 48000.0;
+        assert!((renderer.samples().len() as f64 - expected).abs() <= 1.0);
+    }
+
+    #[test]
+    fn render_midi() {
+        let mut midi = MidiRenderer::new();
+        midi.render(Music::from("T60 L4 C ML > C"));
+        let bytes = midi.to_bytes();
+        assert_eq!(&bytes[..14], b"MThd\0\0\0\x06\0\0\0\x01\x01\xe0");
+        assert_eq!(&bytes[14..18], b"MTrk");
+        // One second for each quarter note at 60 beats a minute, of which
+        // normal notes play for 7/8ths and legato notes for 3/4s
+        #[rustfmt::skip]
+        let track = [
+            0x00, 0xff, 0x51, 0x03, 0x07, 0xa1, 0x20,
+            0x00, 0xc0, 80,
+            0x00, 0xff, 0x51, 0x03, 0x0f, 0x42, 0x40,
+            0x00, 0x90, 84, 100,
+            0x83, 0x24, 0x80, 84, 0,
+            0x3c, 0x90, 96, 100,
+            0x82, 0x68, 0x80, 96, 0,
+            0x78, 0xff, 0x2f, 0x00,
+        ];
+        assert_eq!(&bytes[22..], track);
+    }
+
+    #[test]
+    fn import_mml() {
+        let melody =
+            Melody::from_mml("t150 o4 l8 c d+ e- [f]3 g4 r4 a12 b12 > c12 < b2 & b4").unwrap();
+        assert_eq!(
+            melody.to_string(),
+            "T150O2C8D+8D+8F8F8F8G4P4A12B12O3C12O2B2.",
+        );
+        assert_eq!(
+            melody.to_music().to_string(),
+            "T150 O2 C8 D+8 D+8 F8 F8 F8 G4 P4 A12 B12 O3 C12 O2 B2."
+        );
+        assert!(melody.to_sequence().starts_with(b"\x1b[MFT150"));
+        assert!(melody.to_sequence().ends_with(b"B2.\x0e"));
+    }
+
+    #[test]
+    fn import_round_trip() {
+        let melody = Melody::from_mml("t150 o4 l8 c d e f g4 r4 a16 b16 > c8. < b2.").unwrap();
+        let mut midi = MidiRenderer::new();
+        midi.render(melody.to_music());
+        assert_eq!(Melody::from_midi(&midi.to_bytes()).unwrap(), melody);
+        // The highest note of a chord is taken, and held for a quarter note
+        #[rustfmt::skip]
+        let chord = [
+            b'M', b'T', b'h', b'd', 0, 0, 0, 6, 0, 0, 0, 1, 0, 96,
+            b'M', b'T', b'r', b'k', 0, 0, 0, 20,
+            0x00, 0x90, 60, 100, 0x00, 67, 100, 0x00, 64, 100,
+            0x60, 0x80, 60, 0, 0x00, 67, 0, 0x00, 64, 0,
+        ];
+        assert_eq!(Melody::from_midi(&chord).unwrap().to_string(), "T120O2G4");
+    }
+
+    #[test]
+    fn out_of_range_music_is_clamped() {
+        let mut renderer = Renderer::new(8000);
+        renderer.render(Music::from("O9 C B+ O0 < C- N90 T0 L0 C P0"));
+        assert!(!renderer.samples().is_empty());
+        // Tempos, lengths, and pauses are clamped as they are by the warnings
+        let duration = |music: &str| {
+            let mut renderer = Renderer::new(8000);
+            renderer.render(Music::from(music));
+            renderer.duration()
+        };
+        assert_eq!(duration("T0 L0 C P0"), duration("T32 L1 C P1"));
+        assert_eq!(duration("T999 L99 C P99"), duration("T255 L64 C P64"));
+    }
+
+    #[test]
+    fn pc_speaker_voice() {
+        // The timer can only divide its clock by a whole number
+        assert_eq!(PcSpeaker::quantise(440.0), 1_193_182.0 / 2712.0);
+        assert_eq!(PcSpeaker::quantise(0.0), 0.0);
+        let voice = Voice {
+            waveform: Waveform::PcSpeaker,
+            volume: 0.5,
+        };
+        let mut renderer = Renderer::with_player(Player::with_voice(voice), 48000);
+        renderer.render(Music::from("T120 L4 C"));
+        let samples = renderer.samples();
+        assert!(samples.iter().all(|sample| sample.abs() <= 0.5));
+        assert!(samples.iter().any(|sample| sample.abs() > 0.4));
+    }
+
+    #[test]
+    fn background_music() {
+        let output = AudioOutput::null();
+        let wait_while = |condition: &dyn Fn() -> bool| {
+            let start = Instant::now();
+            while condition() && start.elapsed() < Duration::from_secs(2) {
+                thread::sleep(Duration::from_millis(1));
+            }
+        };
+        let music = Music::from("MB T255 L16 CDEFG");
+        let player_thread = PlayerThread::new(Player::default(), &output, music).unwrap();
+        wait_while(&|| player_thread.is_blocking());
+        assert!(!player_thread.is_blocking());
+        assert!(player_thread.queue(Music::from("MF L64 C")).is_ok());
+        wait_while(&|| !player_thread.finished_playing().unwrap());
+        let player = player_thread.join().unwrap();
+        assert!(!player.is_background());
+        // A full queue holds things up even in the background
+        let music = Music::from(format!("MB T255 L64 {}", "C".repeat(40)).as_str());
+        let player_thread = PlayerThread::new(player, &output, music).unwrap();
+        thread::sleep(Duration::from_millis(20));
+        assert!(player_thread.is_blocking());
+        player_thread.abort().unwrap();
+        assert!(player_thread.join().unwrap().is_background());
+    }
+
+    #[test]
+    fn progress() {
+        let mut player = Player::default();
+        let rx = player.subscribe();
+        let mut renderer = Renderer::with_player(player, 8000);
+        let music = Music::from("T120 L4  C  d#2  900;1");
+        assert_eq!(music.ranges(), &[0..4, 5..7, 9..10, 12..15, 17..22]);
+        renderer.render(music);
+        let progress = rx.try_iter().collect::<Vec<Progress>>();
+        assert_eq!(
+            progress.iter().map(|p| p.index).collect::<Vec<usize>>(),
+            [0, 1, 2, 3, 4]
+        );
+        assert_eq!(progress[1].text, "L4");
+        assert_eq!(progress[1].frequency, None);
+        assert_eq!(progress[2].range, 9..10);
+        assert_eq!(progress[2].frequency, Some(1046.5));
+        assert_eq!(progress[2].duration, Duration::from_millis(501));
+        assert_eq!(progress[3].start, Duration::from_millis(501));
+        assert_eq!(progress[3].frequency, Some(1244.5));
+        assert_eq!(progress[4].frequency, Some(900.0));
+        // Nothing is sent without a subscriber
+        let mut renderer = Renderer::with_player(Player::default(), 8000);
+        renderer.render(Music::from("C"));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn seeded_variation() {
+        let music = "2000;1;6;;*";
+        let mut player = Player::with_seed(2600);
+        let frequencies = Music::from(music)
+            .into_iter()
+            .flat_map(|entity| player.tones(entity))
+            .filter_map(|tone| match tone {
+                Tone::Play(frequency, _) => Some(frequency),
+                _ => None,
+            })
+            .collect::<Vec<f32>>();
+        assert_eq!(
+            frequencies,
+            [2000.0, 1647.927, 1267.5822, 1149.8652, 1645.7657, 1978.4584]
+        );
+        let mut midi = MidiRenderer::with_player(Player::with_seed(2600));
+        midi.render(Music::from(music));
+        assert_eq!(
+            midi.to_bytes(),
+            [
+                77, 84, 104, 100, 0, 0, 0, 6, 0, 0, 0, 1, 1, 224, 77, 84, 114, 107, 0, 0, 0, 110,
+                0, 255, 81, 3, 7, 161, 32, 0, 192, 80, 0, 224, 105, 70, 0, 144, 95, 100, 53, 128,
+                95, 0, 0, 224, 0, 64, 0, 224, 70, 59, 0, 144, 92, 100, 52, 128, 92, 0, 0, 224, 0,
+                64, 0, 224, 23, 74, 0, 144, 87, 100, 53, 128, 87, 0, 0, 224, 0, 64, 0, 224, 23, 52,
+                0, 144, 86, 100, 53, 128, 86, 0, 0, 224, 0, 64, 0, 224, 105, 58, 0, 144, 92, 100,
+                53, 128, 92, 0, 0, 224, 0, 64, 0, 224, 105, 64, 0, 144, 95, 100, 52, 128, 95, 0, 0,
+                224, 0, 64, 0, 255, 47, 0
+            ]
+        );
+        // Renders with the same seed match, including noise
+        let render = |seed: u64, waveform: Waveform| {
+            let mut player = Player::with_seed(seed);
+            player.set_voice(Voice {
+                waveform,
+                ..Voice::default()
+            });
+            let mut renderer = Renderer::with_player(player, 8000);
+            renderer.render(Music::from(music));
+            renderer.samples().to_vec()
+        };
+        assert_eq!(
+            render(2600, Waveform::Square),
+            render(2600, Waveform::Square)
+        );
+        assert_ne!(
+            render(2600, Waveform::Square),
+            render(2601, Waveform::Square)
+        );
+        assert_eq!(render(2600, Waveform::Noise), render(2600, Waveform::Noise));
+        let mut player = Player::with_seed(1);
+        player.set_seed(2600);
+        let mut renderer = Renderer::with_player(player, 8000);
+        renderer.render(Music::from(music));
+        assert_eq!(renderer.samples(), render(2600, Waveform::Square));
+    }
+
+    #[test]
+    fn mixer() {
+        let mut mixer = Mixer::new(AudioOutput::null(), 2);
+        mixer.set_player(1, Player::with_seed(1));
+        assert!(mixer.play(0, Music::from("T255 L16 O4 CDEFG")).is_ok());
+        assert!(mixer.play(1, Music::from("T255 L16 O1 C")).is_ok());
+        assert_eq!(mixer.playing(), 2);
+        assert_eq!(
+            mixer.play(2, Music::from("C")).err(),
+            Some(MixerError::NoFreeVoice)
+        );
+        assert!(mixer.player(2).is_none());
+        // Music on a channel that's playing follows what's already there
+        assert!(mixer.play(1, Music::from("MB")).is_ok());
+        assert!(mixer.player(1).unwrap().is_background());
+        mixer.stop(0);
+        assert!(!mixer.is_playing(0));
+        assert!(mixer.play(2, Music::from("T255 L64 C")).is_ok());
+        mixer.sleep_until_end();
+        assert_eq!(mixer.playing(), 0);
+    }
 }
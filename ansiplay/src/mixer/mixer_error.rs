@@ -0,0 +1,19 @@
+use std::{error, fmt};
+
+/// Custom error used when starting music on a [crate::Mixer]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MixerError {
+    NoFreeVoice,
+    CannotCreateSink,
+}
+
+impl fmt::Display for MixerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MixerError::NoFreeVoice => write!(f, "Every voice is already playing"),
+            MixerError::CannotCreateSink => write!(f, "Cannot create audio sink"),
+        }
+    }
+}
+
+impl error::Error for MixerError {}
@@ -0,0 +1,134 @@
+mod mixer_error;
+use crate::{
+    music::Music,
+    player::{AudioOutput, MusicSource, Player, SAMPLE_RATE},
+};
+use basic_waves::rodio::Sink;
+pub use mixer_error::MixerError;
+use std::collections::BTreeMap;
+
+// A stream of music with its own player, which keeps its tempo, octave, and
+// so on between the music started on it
+struct Channel {
+    player: Player,
+    sink: Option<Sink>,
+}
+
+impl Channel {
+    fn is_playing(&self) -> bool {
+        self.sink.as_ref().is_some_and(|sink| !sink.empty())
+    }
+}
+
+/// Plays several streams of [Music] at once, each tagged by a channel with
+/// its own [Player], so that a bass line can be layered under a melody. Each
+/// channel plays through a sink of its own, which the audio output mixes.
+pub struct Mixer {
+    output: AudioOutput,
+    voices: usize,
+    channels: BTreeMap<usize, Channel>,
+}
+
+impl Mixer {
+    /// Constructs a mixer which plays up to `voices` channels at once
+    /// through `output`
+    pub fn new(output: AudioOutput, voices: usize) -> Mixer {
+        Mixer {
+            output,
+            voices: voices.max(1),
+            channels: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the number of channels which can play at once
+    pub fn voices(&self) -> usize {
+        self.voices
+    }
+
+    /// Changes the number of channels which can play at once, any channels
+    /// beyond it which are already playing carry on
+    pub fn set_voices(&mut self, voices: usize) {
+        self.voices = voices.max(1);
+    }
+
+    /// Sets the player `channel` plays with, which is a fresh [Player] until
+    /// it's set, music already started on the channel is unchanged
+    pub fn set_player(&mut self, channel: usize, player: Player) {
+        match self.channels.get_mut(&channel) {
+            Some(existing) => existing.player = player,
+            None => {
+                self.channels
+                    .insert(channel, Channel { player, sink: None });
+            }
+        }
+    }
+
+    /// Returns the player of `channel`, if one has been set or music started
+    /// on it
+    pub fn player(&self, channel: usize) -> Option<&Player> {
+        self.channels.get(&channel).map(|channel| &channel.player)
+    }
+
+    /// Starts `music` on `channel`, after any music the channel is already
+    /// playing. Fails if the channel isn't playing and every voice is in use.
+    pub fn play(&mut self, channel: usize, music: Music) -> Result<(), MixerError> {
+        if !self.is_playing(channel) && self.playing() >= self.voices {
+            return Err(MixerError::NoFreeVoice);
+        }
+        let channel = self.channels.entry(channel).or_insert_with(|| Channel {
+            player: Player::new(),
+            sink: None,
+        });
+        let sink = match channel.sink {
+            Some(ref sink) => sink,
+            None => channel.sink.insert(
+                self.output
+                    .sink()
+                    .map_err(|_| MixerError::CannotCreateSink)?,
+            ),
+        };
+        // Sound codes' delays hold up the rest of the channel, as they would
+        // in a thread
+        let source = MusicSource::new(&mut channel.player, music.into_spans(), SAMPLE_RATE, true);
+        sink.append(source);
+        Ok(())
+    }
+
+    /// Returns `true` whilst `channel` has music to play
+    pub fn is_playing(&self, channel: usize) -> bool {
+        self.channels
+            .get(&channel)
+            .is_some_and(|channel| channel.is_playing())
+    }
+
+    /// Returns the number of channels with music to play
+    pub fn playing(&self) -> usize {
+        self.channels
+            .values()
+            .filter(|channel| channel.is_playing())
+            .count()
+    }
+
+    /// Stops the music on `channel`, the channel keeps its player
+    pub fn stop(&mut self, channel: usize) {
+        if let Some(channel) = self.channels.get_mut(&channel) {
+            channel.sink = None;
+        }
+    }
+
+    /// Stops the music on every channel
+    pub fn stop_all(&mut self) {
+        for channel in self.channels.values_mut() {
+            channel.sink = None;
+        }
+    }
+
+    /// Blocks the current thread until every channel has finished playing
+    pub fn sleep_until_end(&self) {
+        for channel in self.channels.values() {
+            if let Some(ref sink) = channel.sink {
+                sink.sleep_until_end();
+            }
+        }
+    }
+}
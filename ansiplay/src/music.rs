@@ -1,32 +1,68 @@
-use crate::IntoMusicSequenceIter;
-use std::fmt::{self, Display, Formatter};
+use crate::{music_error::MusicError, IntoMusicSequenceIter};
+use std::{
+    fmt::{self, Display, Formatter},
+    ops::{Range, RangeInclusive},
+};
+
+pub(crate) const DEFAULT_OCTAVE: usize = 4;
+pub(crate) const MAX_OCTAVE: usize = 6;
+/// The number of notes the player can play, seven octaves
+pub(crate) const NOTES: usize = 84;
+pub(crate) const LENGTHS: RangeInclusive<usize> = 1..=64;
+pub(crate) const TEMPOS: RangeInclusive<usize> = 32..=255;
 
 /// A representation of ANSI Music
 #[derive(Clone, Debug)]
 pub struct Music {
     entities: Vec<MusicEntity>,
+    ranges: Vec<Range<usize>>,
+    errors: Vec<MusicError>,
 }
 
 impl From<&str> for Music {
     fn from(string: &str) -> Self {
-        let entities = string
-            .chars()
-            .map(|char| char as u8)
-            .collect::<Vec<u8>>()
-            .as_slice()
-            .into_musical_sequence_iter()
-            .collect::<Vec<MusicEntity>>();
-        Music { entities }
+        let bytes = string.chars().map(|char| char as u8).collect::<Vec<u8>>();
+        Music::new(bytes.as_slice())
     }
 }
 
 impl Music {
     /// Constructs a new instance based on a struct that implements [IntoMusicSequenceIter]
     pub fn new<'a>(iter: impl IntoMusicSequenceIter<'a>) -> Music {
-        let entities = iter
-            .into_musical_sequence_iter()
-            .collect::<Vec<MusicEntity>>();
-        Music { entities }
+        let mut iter = iter.into_musical_sequence_iter();
+        let entities = iter.by_ref().collect::<Vec<MusicEntity>>();
+        let (ranges, errors) = iter.into_parts();
+        Music {
+            entities,
+            ranges,
+            errors,
+        }
+    }
+
+    /// Returns the number of notes, pauses, and sound codes, which would take
+    /// up a place each in GW-BASIC's background queue
+    pub fn notes(&self) -> usize {
+        self.entities
+            .iter()
+            .filter(|entity| entity.is_note())
+            .count()
+    }
+
+    /// Returns where each entity was found in the bytes the music was parsed
+    /// from, in the same order as the entities
+    pub fn ranges(&self) -> &[Range<usize>] {
+        &self.ranges
+    }
+
+    // Pairs every entity with its range
+    pub(crate) fn into_spans(self) -> impl Iterator<Item = (MusicEntity, Range<usize>)> {
+        self.entities.into_iter().zip(self.ranges)
+    }
+
+    /// Returns the problems found whilst parsing the music, the offsets are
+    /// from the start of the bytes it was parsed from
+    pub fn errors(&self) -> &[MusicError] {
+        &self.errors
     }
 }
 
@@ -120,6 +156,42 @@ pub enum Variation {
     Random,
 }
 
+impl MusicEntity {
+    pub(crate) fn is_note(&self) -> bool {
+        matches!(
+            self,
+            MusicEntity::RawNote(_)
+                | MusicEntity::Pause(_)
+                | MusicEntity::Note { .. }
+                | MusicEntity::SoundCode(_)
+        )
+    }
+}
+
+impl Note {
+    pub(crate) fn semitone(&self) -> usize {
+        match self {
+            Note::A => 9,
+            Note::B => 11,
+            Note::C => 0,
+            Note::D => 2,
+            Note::E => 4,
+            Note::F => 5,
+            Note::G => 7,
+        }
+    }
+}
+
+impl NoteSign {
+    pub(crate) fn offset(&self) -> isize {
+        match self {
+            NoteSign::Sharp => 1,
+            NoteSign::Natural => 0,
+            NoteSign::Flat => -1,
+        }
+    }
+}
+
 impl Display for Articulation {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
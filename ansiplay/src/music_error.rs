@@ -0,0 +1,68 @@
+use std::{error, fmt};
+
+/// A problem found whilst parsing ANSI Music
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MusicError {
+    /// The offset of the problem in the bytes of the music
+    pub offset: usize,
+    pub kind: MusicErrorKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MusicErrorKind {
+    UnexpectedCharacter(u8),
+    /// A command, such as `L` or `T`, without its number
+    MissingValue(u8),
+    UnknownOperation(u8),
+    InvalidSoundCode,
+    OctaveOutOfRange,
+    NoteOutOfRange,
+    LengthOutOfRange(usize),
+    TempoOutOfRange(usize),
+    PauseOutOfRange(usize),
+}
+
+impl MusicError {
+    /// Returns `true` for values that are out of range, which the
+    /// [Player](crate::Player) clamps, rather than input it can't understand
+    pub fn is_warning(&self) -> bool {
+        matches!(
+            self.kind,
+            MusicErrorKind::OctaveOutOfRange
+                | MusicErrorKind::NoteOutOfRange
+                | MusicErrorKind::LengthOutOfRange(_)
+                | MusicErrorKind::TempoOutOfRange(_)
+                | MusicErrorKind::PauseOutOfRange(_)
+        )
+    }
+}
+
+impl fmt::Display for MusicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at offset {}", self.kind, self.offset)
+    }
+}
+
+impl fmt::Display for MusicErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MusicErrorKind::UnexpectedCharacter(byte) => {
+                write!(f, "Unexpected character 0x{byte:02x}")
+            }
+            MusicErrorKind::MissingValue(byte) => {
+                write!(f, "Missing value for {}", *byte as char)
+            }
+            MusicErrorKind::UnknownOperation(byte) => {
+                write!(f, "Unknown operation M{}", *byte as char)
+            }
+            MusicErrorKind::InvalidSoundCode => write!(f, "Invalid sound code"),
+            MusicErrorKind::OctaveOutOfRange => write!(f, "Octave out of range"),
+            MusicErrorKind::NoteOutOfRange => write!(f, "Note out of range"),
+            MusicErrorKind::LengthOutOfRange(value) => write!(f, "Length {value} out of range"),
+            MusicErrorKind::TempoOutOfRange(value) => write!(f, "Tempo {value} out of range"),
+            MusicErrorKind::PauseOutOfRange(value) => write!(f, "Pause {value} out of range"),
+        }
+    }
+}
+
+impl error::Error for MusicError {}
@@ -1,15 +1,88 @@
-use crate::music::*;
+use crate::{
+    music::*,
+    music_error::{MusicError, MusicErrorKind},
+};
 use codepage437::ascii;
+use std::ops::{Range, RangeInclusive};
 
 /// A struct that implements [Iterator] that can be used to produce [Music].
+/// Anything that can't be parsed is skipped, and recorded in
+/// [MusicSequenceIterator::errors] along with any values out of range.
 pub struct MusicSequenceIterator<'a> {
     bytes: &'a [u8],
     position: usize,
+    // Follows the octave as the player would, to find notes out of range
+    octave: usize,
+    errors: Vec<MusicError>,
+    // Where the entity being parsed starts
+    start: usize,
+    ranges: Vec<Range<usize>>,
 }
 
 impl<'a> MusicSequenceIterator<'a> {
-    fn new(bytes: &'a [u8]) -> MusicSequenceIterator {
-        MusicSequenceIterator { bytes, position: 0 }
+    fn new(bytes: &'a [u8]) -> MusicSequenceIterator<'a> {
+        MusicSequenceIterator {
+            bytes,
+            position: 0,
+            octave: DEFAULT_OCTAVE,
+            errors: Vec::new(),
+            start: 0,
+            ranges: Vec::new(),
+        }
+    }
+
+    /// Returns the problems found in the music parsed so far
+    pub fn errors(&self) -> &[MusicError] {
+        &self.errors
+    }
+
+    /// Returns where each entity produced so far was found in the bytes
+    pub fn ranges(&self) -> &[Range<usize>] {
+        &self.ranges
+    }
+
+    pub(crate) fn into_parts(self) -> (Vec<Range<usize>>, Vec<MusicError>) {
+        (self.ranges, self.errors)
+    }
+
+    fn error(&mut self, offset: usize, kind: MusicErrorKind) {
+        self.errors.push(MusicError { offset, kind });
+    }
+
+    // Parses the number following a command, recording an error if it's
+    // missing or outside of `range`
+    fn parse_value(
+        &mut self,
+        offset: usize,
+        range: RangeInclusive<usize>,
+        out_of_range: fn(usize) -> MusicErrorKind,
+    ) -> Option<usize> {
+        match self.parse_int(true, false) {
+            Some(value) => {
+                if !range.contains(&value) {
+                    self.error(offset, out_of_range(value));
+                }
+                Some(value)
+            }
+            None => {
+                self.error(offset, MusicErrorKind::MissingValue(self.bytes[offset]));
+                None
+            }
+        }
+    }
+
+    fn parse_note(&mut self, offset: usize, note: Note) -> MusicEntity {
+        let info = self.parse_note_info();
+        if let Some(length) = info.length {
+            if !LENGTHS.contains(&length) {
+                self.error(offset, MusicErrorKind::LengthOutOfRange(length));
+            }
+        }
+        let index = (self.octave * 12 + note.semitone()) as isize + info.sign.offset();
+        if !(0..NOTES as isize).contains(&index) {
+            self.error(offset, MusicErrorKind::NoteOutOfRange);
+        }
+        MusicEntity::Note { note, info }
     }
 
     fn parse_dots(&mut self) -> usize {
@@ -28,27 +101,46 @@ impl<'a> MusicSequenceIterator<'a> {
         amount
     }
 
+    // Only moves past the byte after "M" when it's an operation, as music
+    // starts with the "M" of `ESC[M`, which may be followed by any command
     fn parse_operation(&mut self) -> Option<MusicOperation> {
-        if let Some(byte) = self.bytes.get(self.position) {
-            self.position += 1;
-            match *byte {
-                ascii::UPPERCASE_B => Some(MusicOperation::Background),
-                ascii::UPPERCASE_F => Some(MusicOperation::Foreground),
-                ascii::UPPERCASE_L | ascii::LOWERCASE_L => {
-                    Some(MusicOperation::Articulation(Articulation::Legato))
-                }
-                ascii::UPPERCASE_N | ascii::LOWERCASE_N => {
-                    Some(MusicOperation::Articulation(Articulation::Normal))
-                }
-                ascii::UPPERCASE_S | ascii::LOWERCASE_S => {
-                    Some(MusicOperation::Articulation(Articulation::Stacato))
-                }
-                ascii::SPACE => Some(MusicOperation::None),
-                _ => None,
+        let operation = match *self.bytes.get(self.position)? {
+            ascii::UPPERCASE_B => MusicOperation::Background,
+            ascii::UPPERCASE_F => MusicOperation::Foreground,
+            ascii::UPPERCASE_L | ascii::LOWERCASE_L => {
+                MusicOperation::Articulation(Articulation::Legato)
             }
-        } else {
-            None
-        }
+            ascii::UPPERCASE_N | ascii::LOWERCASE_N => {
+                MusicOperation::Articulation(Articulation::Normal)
+            }
+            ascii::UPPERCASE_S | ascii::LOWERCASE_S => {
+                MusicOperation::Articulation(Articulation::Stacato)
+            }
+            ascii::SPACE => MusicOperation::None,
+            _ => return None,
+        };
+        self.position += 1;
+        Some(operation)
+    }
+
+    // Whether `byte` starts a command, or a sound code
+    fn is_command(byte: u8) -> bool {
+        matches!(
+            byte.to_ascii_uppercase(),
+            ascii::MINUS
+                | ascii::PERIOD
+                | ascii::DIGIT_0..=ascii::DIGIT_9
+                | ascii::SEMI_COLON
+                | ascii::UPPERCASE_A..=ascii::UPPERCASE_G
+                | ascii::UPPERCASE_L
+                | ascii::UPPERCASE_M
+                | ascii::UPPERCASE_N
+                | ascii::UPPERCASE_O
+                | ascii::UPPERCASE_P
+                | ascii::UPPERCASE_T
+                | ascii::LESS_THAN_SIGN
+                | ascii::GREATER_THAN_SIGN
+        )
     }
 
     fn parse_int(&mut self, accept_whitespace: bool, accept_semi_colon: bool) -> Option<usize> {
@@ -177,7 +269,22 @@ impl<'a> Iterator for MusicSequenceIterator<'a> {
     type Item = MusicEntity;
 
     fn next(&mut self) -> Option<Self::Item> {
+        let entity = self.parse_entity()?;
+        // Spaces skipped after the entity aren't part of it
+        let mut end = self.position;
+        while end > self.start && self.bytes[end - 1] == ascii::SPACE {
+            end -= 1;
+        }
+        self.ranges.push(self.start..end);
+        Some(entity)
+    }
+}
+
+impl<'a> MusicSequenceIterator<'a> {
+    fn parse_entity(&mut self) -> Option<MusicEntity> {
         while let Some(byte) = self.bytes.get(self.position).copied() {
+            let offset = self.position;
+            self.start = offset;
             self.position += 1;
             match byte {
                 ascii::MINUS
@@ -185,72 +292,97 @@ impl<'a> Iterator for MusicSequenceIterator<'a> {
                 | ascii::DIGIT_0..=ascii::DIGIT_9
                 | ascii::SEMI_COLON => {
                     self.position -= 1;
-                    return Some(MusicEntity::SoundCode(self.parse_sound_code()?));
+                    let info = self.parse_sound_code()?;
+                    if info.frequency.is_some() != info.duration.is_some() {
+                        self.error(offset, MusicErrorKind::InvalidSoundCode);
+                    }
+                    return Some(MusicEntity::SoundCode(info));
                 }
                 ascii::UPPERCASE_A | ascii::LOWERCASE_A => {
-                    return Some(MusicEntity::Note {
-                        note: Note::A,
-                        info: self.parse_note_info(),
-                    })
+                    return Some(self.parse_note(offset, Note::A))
                 }
                 ascii::UPPERCASE_B | ascii::LOWERCASE_B => {
-                    return Some(MusicEntity::Note {
-                        note: Note::B,
-                        info: self.parse_note_info(),
-                    })
+                    return Some(self.parse_note(offset, Note::B))
                 }
                 ascii::UPPERCASE_C | ascii::LOWERCASE_C => {
-                    return Some(MusicEntity::Note {
-                        note: Note::C,
-                        info: self.parse_note_info(),
-                    })
+                    return Some(self.parse_note(offset, Note::C))
                 }
                 ascii::UPPERCASE_D | ascii::LOWERCASE_D => {
-                    return Some(MusicEntity::Note {
-                        note: Note::D,
-                        info: self.parse_note_info(),
-                    })
+                    return Some(self.parse_note(offset, Note::D))
                 }
                 ascii::UPPERCASE_E | ascii::LOWERCASE_E => {
-                    return Some(MusicEntity::Note {
-                        note: Note::E,
-                        info: self.parse_note_info(),
-                    })
+                    return Some(self.parse_note(offset, Note::E))
                 }
                 ascii::UPPERCASE_F | ascii::LOWERCASE_F => {
-                    return Some(MusicEntity::Note {
-                        note: Note::F,
-                        info: self.parse_note_info(),
-                    })
+                    return Some(self.parse_note(offset, Note::F))
                 }
                 ascii::UPPERCASE_G | ascii::LOWERCASE_G => {
-                    return Some(MusicEntity::Note {
-                        note: Note::G,
-                        info: self.parse_note_info(),
-                    })
+                    return Some(self.parse_note(offset, Note::G))
                 }
                 ascii::UPPERCASE_L | ascii::LOWERCASE_L => {
-                    return self.parse_int(true, false).map(MusicEntity::Length)
-                }
-                ascii::UPPERCASE_M | ascii::LOWERCASE_M => {
-                    return self.parse_operation().map(MusicEntity::Operation)
+                    if let Some(value) =
+                        self.parse_value(offset, LENGTHS, MusicErrorKind::LengthOutOfRange)
+                    {
+                        return Some(MusicEntity::Length(value));
+                    }
                 }
+                ascii::UPPERCASE_M | ascii::LOWERCASE_M => match self.parse_operation() {
+                    Some(operation) => return Some(MusicEntity::Operation(operation)),
+                    // A lone "M", at the end or before another command, is fine
+                    None => {
+                        if let Some(byte) = self.bytes.get(self.position).copied() {
+                            if !Self::is_command(byte) {
+                                self.error(offset, MusicErrorKind::UnknownOperation(byte));
+                                self.position += 1;
+                            }
+                        }
+                    }
+                },
                 ascii::UPPERCASE_N | ascii::LOWERCASE_N => {
-                    return self.parse_int(true, false).map(MusicEntity::RawNote)
+                    if let Some(value) =
+                        self.parse_value(offset, 0..=NOTES - 1, |_| MusicErrorKind::NoteOutOfRange)
+                    {
+                        return Some(MusicEntity::RawNote(value));
+                    }
                 }
                 ascii::UPPERCASE_O | ascii::LOWERCASE_O => {
-                    return self.parse_int(true, false).map(MusicEntity::Octave)
+                    if let Some(value) = self
+                        .parse_value(offset, 0..=MAX_OCTAVE, |_| MusicErrorKind::OctaveOutOfRange)
+                    {
+                        self.octave = value.min(MAX_OCTAVE);
+                        return Some(MusicEntity::Octave(value));
+                    }
                 }
                 ascii::UPPERCASE_P | ascii::LOWERCASE_P => {
-                    return Some(MusicEntity::Pause(self.parse_int(true, false)?))
+                    if let Some(value) =
+                        self.parse_value(offset, LENGTHS, MusicErrorKind::PauseOutOfRange)
+                    {
+                        return Some(MusicEntity::Pause(value));
+                    }
                 }
                 ascii::UPPERCASE_T | ascii::LOWERCASE_T => {
-                    return self.parse_int(true, false).map(MusicEntity::Tempo)
+                    if let Some(value) =
+                        self.parse_value(offset, TEMPOS, MusicErrorKind::TempoOutOfRange)
+                    {
+                        return Some(MusicEntity::Tempo(value));
+                    }
+                }
+                ascii::LESS_THAN_SIGN => {
+                    if self.octave == 0 {
+                        self.error(offset, MusicErrorKind::OctaveOutOfRange);
+                    }
+                    self.octave = self.octave.saturating_sub(1);
+                    return Some(MusicEntity::DecreaseOctave);
+                }
+                ascii::GREATER_THAN_SIGN => {
+                    if self.octave == MAX_OCTAVE {
+                        self.error(offset, MusicErrorKind::OctaveOutOfRange);
+                    }
+                    self.octave = (self.octave + 1).min(MAX_OCTAVE);
+                    return Some(MusicEntity::IncreaseOctave);
                 }
-                ascii::LESS_THAN_SIGN => return Some(MusicEntity::DecreaseOctave),
-                ascii::GREATER_THAN_SIGN => return Some(MusicEntity::IncreaseOctave),
                 ascii::SPACE => {}
-                _ => {}
+                _ => self.error(offset, MusicErrorKind::UnexpectedCharacter(byte)),
             }
         }
         None
@@ -270,7 +402,10 @@ impl<'a> IntoMusicSequenceIter<'a> for &'a [u8] {
 
 #[cfg(test)]
 mod test {
-    use crate::MusicSequenceIterator;
+    use crate::{
+        music::{MusicEntity, Note},
+        MusicErrorKind, MusicSequenceIterator,
+    };
     fn to_bytes(string: &str) -> Vec<u8> {
         string.chars().map(|char| char as u8).collect::<Vec<u8>>()
     }
@@ -358,4 +493,48 @@ mod test {
         assert_eq!(test_number("-9. ").unwrap(), -9.0);
         assert_eq!(test_number("-. ").unwrap(), 0.0);
     }
+
+    #[test]
+    fn test_bare_m() {
+        let bytes = to_bytes("MT120C");
+        let mut mus = MusicSequenceIterator::new(&bytes);
+        let entities = mus.by_ref().collect::<Vec<_>>();
+        assert!(matches!(entities[0], MusicEntity::Tempo(120)));
+        assert!(matches!(
+            entities[1],
+            MusicEntity::Note { note: Note::C, .. }
+        ));
+        assert_eq!(entities.len(), 2);
+        assert!(mus.errors().is_empty());
+    }
+
+    #[test]
+    fn test_errors() {
+        let bytes = to_bytes("O0 C- < L0 O9 N90 P T20 MX B+ 440 Z");
+        let mut mus = MusicSequenceIterator::new(&bytes);
+        assert_eq!(mus.by_ref().count(), 9);
+        let errors = mus
+            .errors()
+            .iter()
+            .map(|error| (error.offset, error.kind.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            vec![
+                (3, MusicErrorKind::NoteOutOfRange),
+                (6, MusicErrorKind::OctaveOutOfRange),
+                (8, MusicErrorKind::LengthOutOfRange(0)),
+                (11, MusicErrorKind::OctaveOutOfRange),
+                (14, MusicErrorKind::NoteOutOfRange),
+                (18, MusicErrorKind::MissingValue(b'P')),
+                (20, MusicErrorKind::TempoOutOfRange(20)),
+                (24, MusicErrorKind::UnknownOperation(b'X')),
+                (27, MusicErrorKind::NoteOutOfRange),
+                (30, MusicErrorKind::InvalidSoundCode),
+                (34, MusicErrorKind::UnexpectedCharacter(b'Z')),
+            ]
+        );
+        assert!(!mus.errors()[9].is_warning());
+        assert!(mus.errors()[0].is_warning());
+    }
 }
@@ -0,0 +1,66 @@
+use crate::player::PlayerError;
+use basic_waves::rodio::{
+    queue::SourcesQueueOutput, OutputStream, OutputStreamHandle, Sink, Source,
+};
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Where [Player](crate::Player) sends its music, either an audio device or a
+/// null output which discards the sound but still takes as long to play it,
+/// so that anything waiting on the music keeps its pacing.
+pub struct AudioOutput {
+    device: Option<(OutputStream, OutputStreamHandle)>,
+}
+
+impl AudioOutput {
+    /// Opens the default audio device, falling back to the null output if
+    /// there isn't one
+    pub fn try_default() -> AudioOutput {
+        AudioOutput {
+            device: OutputStream::try_default().ok(),
+        }
+    }
+
+    /// Constructs a null output, which plays silently
+    pub fn null() -> AudioOutput {
+        AudioOutput { device: None }
+    }
+
+    /// Returns `true` if this is a null output
+    pub fn is_null(&self) -> bool {
+        self.device.is_none()
+    }
+
+    /// Creates a new [Sink] which plays through this output
+    pub fn sink(&self) -> Result<Sink, PlayerError> {
+        match self.device {
+            Some((_, ref handle)) => Sink::try_new(handle).map_err(|_| PlayerError::ThreadError),
+            None => {
+                let (sink, queue) = Sink::new_idle();
+                thread::spawn(move || drain(queue));
+                Ok(sink)
+            }
+        }
+    }
+}
+
+// Consumes the samples of the queue in real time, which ends once its Sink is
+// dropped
+fn drain(mut queue: SourcesQueueOutput<f32>) {
+    let start = Instant::now();
+    let mut elapsed = 0.0;
+    loop {
+        let samples_per_second = (queue.sample_rate() * queue.channels() as u32).max(1) as f64;
+        let chunk = (samples_per_second / 100.0).ceil() as usize;
+        let consumed = queue.by_ref().take(chunk).count();
+        if consumed == 0 {
+            return;
+        }
+        elapsed += consumed as f64 / samples_per_second;
+        if let Some(wait) = Duration::from_secs_f64(elapsed).checked_sub(start.elapsed()) {
+            thread::sleep(wait);
+        }
+    }
+}
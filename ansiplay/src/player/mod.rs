@@ -1,17 +1,29 @@
+mod audio_output;
+mod music_source;
+mod pc_speaker;
 mod player_error;
 mod player_thread;
+mod progress;
+mod voice;
 use crate::music::*;
-use basic_waves::{
-    rodio::{Sink, Source},
-    SquareWave,
-};
+pub use audio_output::AudioOutput;
+use basic_waves::rodio::{Sink, Source};
+pub(crate) use music_source::MusicSource;
+pub use pc_speaker::PcSpeaker;
 use player_error::PlayerError;
 pub use player_thread::PlayerThread;
 use player_thread::ThreadMessage;
+pub use progress::Progress;
 use rand::prelude::*;
-use std::{sync::mpsc, thread, time};
+use std::{
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread, time,
+};
+pub use voice::{Voice, Waveform};
 
-const FREQS: [f32; 84] = [
+pub(crate) const SAMPLE_RATE: u32 = 48000;
+
+const FREQS: [f32; NOTES] = [
     65.406, 69.296, 73.416, 77.782, 82.406, 87.308, 92.498, 97.998, 103.826, 110.0, 116.54, 123.47,
     130.812, 138.592, 146.832, 155.564, 164.821, 174.614, 185.0, 195.998, 207.66, 220.0, 233.08,
     246.94, 261.62, 277.18, 296.66, 311.12, 329.62, 349.22, 370.0, 392.0, 415.3, 440.0, 466.16,
@@ -22,6 +34,31 @@ const FREQS: [f32; 84] = [
     7040.0, 7458.6, 7902.2,
 ];
 
+/// A sound made by the [Player], lasting a number of milliseconds
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Tone {
+    /// The player's voice at a frequency
+    Play(f32, f64),
+    /// A note from the player's scale, by its index from the C two octaves
+    /// below middle C
+    Note(usize, f64),
+    /// Silence
+    Pause(f64),
+    /// A sound code's delay, which only holds up a player that is running in
+    /// a thread
+    Wait(f64),
+}
+
+impl Tone {
+    // Replaces a note from the scale with its frequency
+    pub(crate) fn with_frequency(self) -> Tone {
+        match self {
+            Tone::Note(index, ms) => Tone::Play(FREQS[index], ms),
+            tone => tone,
+        }
+    }
+}
+
 /// A struct which provides an interface to play [Music], or play music in a new thread.
 #[derive(Debug)]
 pub struct Player {
@@ -30,7 +67,11 @@ pub struct Player {
     octave: usize,
     articulation: Articulation,
     rx: Option<mpsc::Receiver<ThreadMessage>>,
+    aborted: bool,
     rng: StdRng,
+    voice: Voice,
+    background: bool,
+    subscriber: Option<Sender<Progress>>,
 }
 
 impl Default for Player {
@@ -38,10 +79,14 @@ impl Default for Player {
         Self {
             tempo: 120,
             length: 1,
-            octave: 4,
+            octave: DEFAULT_OCTAVE,
             articulation: Articulation::Normal,
             rx: None,
+            aborted: false,
             rng: StdRng::seed_from_u64(random()),
+            voice: Voice::default(),
+            background: false,
+            subscriber: None,
         }
     }
 }
@@ -52,26 +97,83 @@ impl Player {
         Default::default()
     }
 
-    fn get_frequency(&self, note: Note, sign: NoteSign) -> f32 {
-        let mut index = self.octave * 12;
-        index += match note {
-            Note::A => 9,
-            Note::B => 11,
-            Note::C => 0,
-            Note::D => 2,
-            Note::E => 4,
-            Note::F => 5,
-            Note::G => 7,
-        };
-        match sign {
-            NoteSign::Sharp => FREQS[index + 1],
-            NoteSign::Natural => FREQS[index],
-            NoteSign::Flat => FREQS[index - 1],
+    /// Constructs a player which plays with `voice`
+    pub fn with_voice(voice: Voice) -> Player {
+        Player {
+            voice,
+            ..Default::default()
         }
     }
 
-    fn calculate_length(&self, length: Option<usize>, dots: usize) -> (usize, usize) {
-        let length = length.unwrap_or(self.length);
+    /// Constructs a player whose random variations of sound codes, and
+    /// noise, are the same every time for the same `seed`
+    pub fn with_seed(seed: u64) -> Player {
+        Player {
+            rng: StdRng::seed_from_u64(seed),
+            ..Default::default()
+        }
+    }
+
+    /// Restarts the random variations of sound codes, and noise, from `seed`
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    pub fn voice(&self) -> Voice {
+        self.voice
+    }
+
+    pub fn set_voice(&mut self, voice: Voice) {
+        self.voice = voice;
+    }
+
+    /// Returns `true` after `MB`, when music should play in the background
+    /// whilst anything waiting on it carries on, until `MF` is played
+    pub fn is_background(&self) -> bool {
+        self.background
+    }
+
+    /// Returns a receiver of [Progress] for every entity of music played from
+    /// now on, including by a [PlayerThread], this replaces any earlier
+    /// receiver
+    pub fn subscribe(&mut self) -> Receiver<Progress> {
+        let (tx, rx) = mpsc::channel();
+        self.subscriber = Some(tx);
+        rx
+    }
+
+    /// Stops sending [Progress], which disconnects the receiver
+    pub fn unsubscribe(&mut self) {
+        self.subscriber = None;
+    }
+
+    // Returns an infinite source of the player's voice playing `frequency`
+    pub(crate) fn source(
+        &mut self,
+        frequency: f32,
+        sample_rate: u32,
+    ) -> Box<dyn Source<Item = f32> + Send> {
+        let seed = match self.voice.waveform {
+            Waveform::Noise => self.rng.gen(),
+            _ => 0,
+        };
+        self.voice.source(frequency, sample_rate, seed)
+    }
+
+    pub(crate) fn tempo(&self) -> usize {
+        self.tempo
+    }
+
+    // Notes beyond the lowest or highest notes are clamped
+    fn get_index(&self, note: Note, sign: NoteSign) -> usize {
+        let index = (self.octave * 12 + note.semitone()) as isize + sign.offset();
+        index.clamp(0, NOTES as isize - 1) as usize
+    }
+
+    fn calculate_length(&self, length: Option<usize>, dots: usize) -> (f64, f64) {
+        let length = length
+            .unwrap_or(self.length)
+            .clamp(*LENGTHS.start(), *LENGTHS.end());
         let full_note = 60.0 * 1000.0 / self.tempo as f32 * 4.0 / length as f32;
         let mut note_length = full_note;
         match self.articulation {
@@ -87,121 +189,125 @@ impl Player {
             }
         }
         (
-            (note_length + extra).ceil() as usize,
-            (full_note - note_length).ceil() as usize,
+            (note_length + extra).ceil() as f64,
+            (full_note - note_length).ceil() as f64,
         )
     }
 
-    fn play_pause(&self, pause_ms: usize, sink: &Sink) {
-        if pause_ms > 0 {
-            let source = SquareWave::new(0.0, 48000)
-                .take_duration(time::Duration::from_millis(pause_ms as u64))
-                .amplify(0.0);
-            sink.append(source);
-        }
-    }
-
-    fn play_frequency(&self, frequency: f32, play_ms: usize, pause_ms: usize, sink: &Sink) {
-        if play_ms > 0 {
-            let source = SquareWave::new(frequency, 48000)
-                .amplify(0.1)
-                .take_duration(time::Duration::from_millis(play_ms as u64));
-            sink.append(source);
-        }
-        self.play_pause(pause_ms, sink);
-    }
-
-    fn play_sound_code(&mut self, info: SoundCodeInfo, sink: &Sink) {
+    fn play_sound_code(&mut self, info: SoundCodeInfo) -> Vec<Tone> {
+        let mut tones = Vec::new();
         if let (Some(mut frequency), Some(duration)) = (info.frequency, info.duration) {
-            let play_ms = (duration / 18.2 * 1000.0).ceil() as usize;
-            let pause_ms = info.delay.unwrap_or(0);
+            // Durations are in ticks of the PC's clock, which runs at 18.2Hz
+            let play_ms = duration as f64 / 18.2 * 1000.0;
+            let pause_ms = info.delay.unwrap_or(0) as f64;
             let cycles = info.cycles.unwrap_or(1);
             if cycles == 0 {
-                self.play_frequency(frequency, play_ms, pause_ms, sink);
+                tones.push(Tone::Play(frequency, play_ms));
             } else {
                 for _ in 0..cycles {
-                    self.play_frequency(frequency, play_ms, 0, sink);
+                    tones.push(Tone::Play(frequency, play_ms));
                     frequency += match info.variation {
                         Some(Variation::Value(value)) => value,
                         Some(Variation::Random) => self.rng.gen_range(-512.0..=512.0),
                         None => 0.0,
                     };
                 }
-                self.play_pause(pause_ms, sink);
             }
+            tones.push(Tone::Pause(pause_ms));
         } else if let Some(delay) = info.delay {
-            if let Some(ref rx) = self.rx {
-                let cycles = (delay as f32 / (1000.0 / 60.0)).floor() as usize;
-                let dur = time::Duration::from_millis(1000 / 60);
-                // Clear the channel, so any buffered messages are ignored.
-                while let Ok(message) = rx.try_recv() {
-                    match message {
-                        ThreadMessage::Interrupt => continue,
-                        ThreadMessage::Abort => return,
-                    }
-                }
-                for _ in 0..cycles {
-                    // Interrupt if a message is received.
-                    if rx.try_recv().is_ok() {
-                        break;
-                    }
-                    thread::sleep(dur);
-                }
-            }
+            tones.push(Tone::Wait(delay as f64));
         }
+        tones
     }
 
-    fn pause(&self, quarter_notes: usize, sink: &Sink) {
-        let pause_ms = 60.0 * 1000.0 / self.tempo as f32 * 4.0 / quarter_notes as f32;
-        self.play_pause(pause_ms as usize, sink)
+    fn pause(&self, quarter_notes: usize) -> Tone {
+        let quarter_notes = quarter_notes.clamp(*LENGTHS.start(), *LENGTHS.end());
+        Tone::Pause(60.0 * 1000.0 / self.tempo as f64 * 4.0 / quarter_notes as f64)
     }
 
-    fn play_note(&self, note: Note, info: NoteInfo, sink: &Sink) {
+    fn play_note(&self, note: Note, info: NoteInfo) -> Vec<Tone> {
         let (play_ms, pause_ms) = self.calculate_length(info.length, info.dots);
-        let frequency = self.get_frequency(note, info.sign);
-        self.play_frequency(frequency, play_ms, pause_ms, sink);
+        let index = self.get_index(note, info.sign);
+        vec![Tone::Note(index, play_ms), Tone::Pause(pause_ms)]
     }
 
-    fn play_raw_note(&self, value: usize, sink: &Sink) {
+    fn play_raw_note(&self, value: usize) -> Vec<Tone> {
         let (play_ms, pause_ms) = self.calculate_length(None, 0);
-        let frequency = FREQS[value];
-        self.play_frequency(frequency, play_ms, pause_ms, sink);
+        vec![
+            Tone::Note(value.min(NOTES - 1), play_ms),
+            Tone::Pause(pause_ms),
+        ]
     }
 
-    /// Plays an atomic element of music through the supplied [Sink] and blocks the current thread.
-    pub fn play_entity(&mut self, entity: MusicEntity, sink: &Sink) {
+    /// Updates the player's state with an atomic element of music, and
+    /// returns the sounds that it makes
+    pub(crate) fn tones(&mut self, entity: MusicEntity) -> Vec<Tone> {
         match entity {
             MusicEntity::Operation(MusicOperation::Articulation(articulation)) => {
                 self.articulation = articulation;
             }
-            MusicEntity::Operation(_operation) => {}
-            MusicEntity::Tempo(value) => self.tempo = value,
-            MusicEntity::Octave(value) => self.octave = value,
-            MusicEntity::Length(value) => self.length = value,
-            MusicEntity::RawNote(value) => self.play_raw_note(value, sink),
-            MusicEntity::Pause(value) => self.pause(value, sink),
-            MusicEntity::IncreaseOctave => self.octave += 1,
-            MusicEntity::DecreaseOctave => self.octave -= 1,
-            MusicEntity::Note { note, info } => self.play_note(note, info, sink),
-            MusicEntity::SoundCode(info) => self.play_sound_code(info, sink),
-        }
-        if let Some(ref rx) = self.rx {
-            let dur = time::Duration::from_millis(1000 / 60);
-            while !sink.empty() {
-                thread::sleep(dur);
-                if let Ok(ThreadMessage::Abort) = rx.try_recv() {
-                    break;
-                }
+            MusicEntity::Operation(MusicOperation::Foreground) => self.background = false,
+            MusicEntity::Operation(MusicOperation::Background) => self.background = true,
+            MusicEntity::Operation(MusicOperation::None) => {}
+            MusicEntity::Tempo(value) => self.tempo = value.clamp(*TEMPOS.start(), *TEMPOS.end()),
+            MusicEntity::Octave(value) => self.octave = value.min(MAX_OCTAVE),
+            MusicEntity::Length(value) => {
+                self.length = value.clamp(*LENGTHS.start(), *LENGTHS.end())
             }
-        } else {
-            sink.sleep_until_end();
+            MusicEntity::RawNote(value) => return self.play_raw_note(value),
+            MusicEntity::Pause(value) => return vec![self.pause(value)],
+            MusicEntity::IncreaseOctave => self.octave = (self.octave + 1).min(MAX_OCTAVE),
+            MusicEntity::DecreaseOctave => self.octave = self.octave.saturating_sub(1),
+            MusicEntity::Note { note, info } => return self.play_note(note, info),
+            MusicEntity::SoundCode(info) => return self.play_sound_code(info),
         }
+        Vec::new()
+    }
+
+    /// Plays an atomic element of music through the supplied [Sink] and blocks the current thread.
+    pub fn play_entity(&mut self, entity: MusicEntity, sink: &Sink) {
+        let source = MusicSource::new(self, [(entity, 0..0)], SAMPLE_RATE, self.rx.is_some());
+        self.play_source(source, sink, |_| {});
     }
 
     /// Plays [Music] through the supplied [Sink] and blocks the current thread.
     pub fn play(&mut self, music: Music, sink: &Sink) {
-        for entity in music {
-            self.play_entity(entity, sink);
+        self.aborted = false;
+        let source = MusicSource::new(self, music.into_spans(), SAMPLE_RATE, self.rx.is_some());
+        self.play_source(source, sink, |_| {});
+    }
+
+    // Blocks until `source` has played, or the thread is told to abort,
+    // `progress` is called with the number of entities that have finished
+    // whilst waiting. The timing is kept by the source, so this only has to
+    // answer messages.
+    pub(crate) fn play_source(
+        &mut self,
+        source: MusicSource,
+        sink: &Sink,
+        mut progress: impl FnMut(usize),
+    ) {
+        let control = source.control();
+        sink.append(source);
+        match self.rx {
+            Some(ref rx) => {
+                let dur = time::Duration::from_millis(1000 / 60);
+                while !sink.empty() {
+                    match rx.recv_timeout(dur) {
+                        Ok(ThreadMessage::Interrupt) => control.interrupt(),
+                        Ok(ThreadMessage::Abort) => {
+                            self.aborted = true;
+                            sink.stop();
+                            break;
+                        }
+                        Err(RecvTimeoutError::Timeout) => {}
+                        Err(RecvTimeoutError::Disconnected) => thread::sleep(dur),
+                    }
+                    progress(control.position());
+                }
+            }
+            None => sink.sleep_until_end(),
         }
+        progress(control.position());
     }
 }
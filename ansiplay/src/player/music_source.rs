@@ -0,0 +1,220 @@
+use crate::{
+    music::MusicEntity,
+    player::{Player, Progress, Tone},
+};
+use basic_waves::rodio::Source;
+use std::{
+    collections::VecDeque,
+    ops::Range,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc::Sender,
+        Arc,
+    },
+    time::Duration,
+};
+
+/// Shared between a [MusicSource] and whatever is waiting on it
+#[derive(Debug, Default)]
+pub(crate) struct Control {
+    interrupt: AtomicBool,
+    // The number of entities that have finished playing
+    position: AtomicUsize,
+}
+
+impl Control {
+    /// Cuts short the delay of a sound code that's playing, or about to
+    pub(crate) fn interrupt(&self) {
+        self.interrupt.store(true, Ordering::Relaxed);
+    }
+
+    pub(crate) fn position(&self) -> usize {
+        self.position.load(Ordering::Relaxed)
+    }
+}
+
+/// What an entity did to the player's queue
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Mark {
+    pub(crate) is_note: bool,
+    pub(crate) background: bool,
+}
+
+struct Segment {
+    // Silent without a wave
+    wave: Option<Box<dyn Source<Item = f32> + Send>>,
+    remaining: usize,
+    wait: bool,
+    started: bool,
+    entity: usize,
+}
+
+/// A source which plays a sequence of entities with every note starting on
+/// the exact sample it's due, the player's state is updated as it's
+/// constructed, so the sound of the music is decided up front.
+pub(crate) struct MusicSource {
+    segments: VecDeque<Segment>,
+    sample_rate: u32,
+    entities: usize,
+    marks: Vec<Mark>,
+    control: Arc<Control>,
+    // Sent as the entities are reached, when the player has a subscriber
+    progress: VecDeque<Progress>,
+    subscriber: Option<Sender<Progress>>,
+}
+
+impl MusicSource {
+    /// Schedules `entities`, along with where they were found in the bytes
+    /// of the music, `waits` decides whether the delays of sound
+    /// codes are played as silence, as they are when something is waiting on
+    /// the music
+    pub(crate) fn new(
+        player: &mut Player,
+        entities: impl IntoIterator<Item = (MusicEntity, Range<usize>)>,
+        sample_rate: u32,
+        waits: bool,
+    ) -> MusicSource {
+        let sample_rate = sample_rate.max(1);
+        let mut segments = VecDeque::new();
+        let mut marks = Vec::new();
+        let mut progress = VecDeque::new();
+        // Boundaries are calculated from the total time so far, so that
+        // rounding never accumulates
+        let mut elapsed_ms = 0.0;
+        let samples = |ms: f64| (ms * sample_rate as f64 / 1000.0).floor() as usize;
+        for (entity, (music_entity, range)) in entities.into_iter().enumerate() {
+            let is_note = music_entity.is_note();
+            let text = music_entity.to_string();
+            let start_ms = elapsed_ms;
+            let mut frequency = None;
+            for tone in player.tones(music_entity) {
+                let (wave, ms, wait) = match tone.with_frequency() {
+                    Tone::Play(play, ms) => {
+                        frequency = frequency.or(Some(play));
+                        (Some(player.source(play, sample_rate)), ms, false)
+                    }
+                    Tone::Pause(ms) => (None, ms, false),
+                    Tone::Wait(ms) if waits => (None, ms, true),
+                    Tone::Note(..) | Tone::Wait(_) => continue,
+                };
+                let remaining = samples(elapsed_ms + ms) - samples(elapsed_ms);
+                elapsed_ms += ms;
+                if remaining > 0 {
+                    segments.push_back(Segment {
+                        wave,
+                        remaining,
+                        wait,
+                        started: false,
+                        entity,
+                    });
+                }
+            }
+            marks.push(Mark {
+                is_note,
+                background: player.background,
+            });
+            if player.subscriber.is_some() {
+                progress.push_back(Progress {
+                    index: entity,
+                    range,
+                    text,
+                    frequency,
+                    start: Duration::from_secs_f64(start_ms / 1000.0),
+                    duration: Duration::from_secs_f64((elapsed_ms - start_ms) / 1000.0),
+                });
+            }
+        }
+        MusicSource {
+            segments,
+            sample_rate,
+            entities: marks.len(),
+            marks,
+            control: Arc::new(Control::default()),
+            progress,
+            subscriber: player.subscriber.clone(),
+        }
+    }
+
+    // Sends the progress of every entity up to and including `entity`, the
+    // entities which make no sound are sent along with the next that does
+    fn send_progress(&mut self, entity: usize) {
+        let Some(ref subscriber) = self.subscriber else {
+            return;
+        };
+        while self
+            .progress
+            .front()
+            .is_some_and(|progress| progress.index <= entity)
+        {
+            let progress = self.progress.pop_front().expect("progress");
+            // Nobody listening isn't a problem for the music
+            subscriber.send(progress).ok();
+        }
+    }
+
+    pub(crate) fn control(&self) -> Arc<Control> {
+        Arc::clone(&self.control)
+    }
+
+    /// Returns a mark for every entity, in order
+    pub(crate) fn marks(&self) -> &[Mark] {
+        &self.marks
+    }
+}
+
+impl Iterator for MusicSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        loop {
+            let Some(segment) = self.segments.front_mut() else {
+                self.control
+                    .position
+                    .store(self.entities, Ordering::Relaxed);
+                self.send_progress(self.entities);
+                return None;
+            };
+            if !segment.started {
+                segment.started = true;
+                self.control
+                    .position
+                    .store(segment.entity, Ordering::Relaxed);
+                // Only an interrupt during the delay cuts it short
+                if segment.wait {
+                    self.control.interrupt.store(false, Ordering::Relaxed);
+                }
+                let entity = segment.entity;
+                self.send_progress(entity);
+                continue;
+            }
+            let interrupted = segment.wait && self.control.interrupt.load(Ordering::Relaxed);
+            if segment.remaining == 0 || interrupted {
+                self.segments.pop_front();
+                continue;
+            }
+            segment.remaining -= 1;
+            return match segment.wave {
+                Some(ref mut wave) => wave.next(),
+                None => Some(0.0),
+            };
+        }
+    }
+}
+
+impl Source for MusicSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
@@ -0,0 +1,94 @@
+use basic_waves::rodio::Source;
+use std::{f32::consts::PI, time::Duration};
+
+/// The frequency of the IBM PC's 8253 programmable interval timer
+const PIT_FREQUENCY: f32 = 1_193_182.0;
+const MAX_DIVISOR: f32 = 65535.0;
+// The speaker cone can't follow the sharp edges of the timer's output
+const SPEAKER_CUTOFF: f32 = 5000.0;
+
+/// An infinite source that sounds like the IBM PC's speaker, which can only
+/// play frequencies that the timer reaches by dividing its clock, and can
+/// only be on or off.
+/// Has a definable sample rate and one channel.
+#[derive(Debug, Clone)]
+pub struct PcSpeaker {
+    freq: f32,
+    sample_rate: u32,
+    // The position in the current cycle, from 0 to 1
+    phase: f32,
+    // The output of the speaker's low-pass filter, and its coefficient
+    level: f32,
+    smoothing: f32,
+}
+
+impl PcSpeaker {
+    /// The frequency, which is quantised to the timer, and sample rate
+    pub fn new(freq: f32, sample_rate: u32) -> PcSpeaker {
+        let sample_rate = sample_rate.max(1);
+        PcSpeaker {
+            freq: PcSpeaker::quantise(freq),
+            sample_rate,
+            phase: 0.0,
+            level: 0.0,
+            smoothing: 1.0 - (-2.0 * PI * SPEAKER_CUTOFF / sample_rate as f32).exp(),
+        }
+    }
+
+    /// Returns the frequency that the timer actually plays for `freq`, or 0
+    /// for silence
+    pub fn quantise(freq: f32) -> f32 {
+        if freq <= 0.0 {
+            return 0.0;
+        }
+        let divisor = (PIT_FREQUENCY / freq).round().clamp(1.0, MAX_DIVISOR);
+        PIT_FREQUENCY / divisor
+    }
+}
+
+impl Iterator for PcSpeaker {
+    type Item = f32;
+
+    #[inline]
+    fn next(&mut self) -> Option<f32> {
+        let step = self.freq / self.sample_rate as f32;
+        // The fraction of this sample for which the speaker is pushed out,
+        // averaged over every cycle that it covers
+        let on = if step == 0.0 {
+            0.0
+        } else {
+            let high = |phase: f32| phase.floor() * 0.5 + (phase.fract()).min(0.5);
+            (high(self.phase + step) - high(self.phase)) / step
+        };
+        self.phase = (self.phase + step).fract();
+        let target = if self.freq == 0.0 {
+            0.0
+        } else {
+            on * 2.0 - 1.0
+        };
+        self.level += (target - self.level) * self.smoothing;
+        Some(self.level)
+    }
+}
+
+impl Source for PcSpeaker {
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
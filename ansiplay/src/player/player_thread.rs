@@ -1,47 +1,132 @@
 use crate::{
     music::*,
-    player::{Player, PlayerError},
+    player::{AudioOutput, MusicSource, Player, PlayerError, SAMPLE_RATE},
 };
-use basic_waves::rodio::{OutputStreamHandle, Sink};
 use std::{
-    sync::mpsc::{self, Receiver, Sender, TryRecvError},
+    collections::VecDeque,
+    sync::{
+        mpsc::{self, Receiver, Sender, TryRecvError},
+        Arc, Mutex,
+    },
     thread::{self, JoinHandle},
 };
 
+/// The number of notes GW-BASIC's background queue holds, once it's full
+/// anything waiting on the music is held up again
+const BACKGROUND_NOTES: usize = 32;
+
 pub enum ThreadMessage {
     Interrupt,
     Abort,
 }
 
+// The state shared between the thread and its owner
+#[derive(Debug, Default)]
+struct Queue {
+    music: VecDeque<Music>,
+    // The notes left to play, in the queue and the music that's playing
+    notes: usize,
+    background: bool,
+    finished: bool,
+    // Applied before the next music in the queue is played
+    seed: Option<u64>,
+}
+
 /// A representation of a threaded instance of [Player]
 #[derive(Debug)]
 pub struct PlayerThread {
     handle: JoinHandle<Player>,
     rx: Receiver<()>,
     tx: Sender<ThreadMessage>,
+    queue: Arc<Mutex<Queue>>,
 }
 
 impl PlayerThread {
     /// Consumes [Player] and immediately starts playing music.
     pub fn new(
         mut player: Player,
-        stream_handle: &OutputStreamHandle,
+        output: &AudioOutput,
         music: Music,
     ) -> Result<PlayerThread, PlayerError> {
         let (player_tx, rx) = mpsc::channel();
         let (tx, interrupt_rx) = mpsc::channel();
         player.rx = Some(interrupt_rx);
-        match Sink::try_new(stream_handle) {
-            Ok(sink) => {
-                let handle = thread::spawn(move || {
-                    player.play(music, &sink);
-                    player_tx.send(()).ok();
-                    player
+        let sink = output.sink()?;
+        let queue = Arc::new(Mutex::new(Queue {
+            notes: music.notes(),
+            background: player.background,
+            ..Default::default()
+        }));
+        let thread_queue = Arc::clone(&queue);
+        let handle = thread::spawn(move || {
+            let mut next = Some(music);
+            while let Some(music) = next {
+                player.aborted = false;
+                if let Some(seed) = thread_queue.lock().expect("queue").seed.take() {
+                    player.set_seed(seed);
+                }
+                let source = MusicSource::new(&mut player, music.into_spans(), SAMPLE_RATE, true);
+                let marks = source.marks().to_vec();
+                let mut finished = 0;
+                player.play_source(source, &sink, |position| {
+                    let position = position.min(marks.len());
+                    if position > finished {
+                        let mut queue = thread_queue.lock().expect("queue");
+                        let notes = marks[finished..position]
+                            .iter()
+                            .filter(|mark| mark.is_note)
+                            .count();
+                        queue.notes = queue.notes.saturating_sub(notes);
+                        queue.background = marks[position - 1].background;
+                        finished = position;
+                    }
                 });
-                Ok(PlayerThread { handle, rx, tx })
+                let mut queue = thread_queue.lock().expect("queue");
+                next = if player.aborted {
+                    None
+                } else {
+                    queue.music.pop_front()
+                };
+                if next.is_none() {
+                    queue.finished = true;
+                }
             }
-            Err(_) => Err(PlayerError::ThreadError),
+            player_tx.send(()).ok();
+            player
+        });
+        Ok(PlayerThread {
+            handle,
+            rx,
+            tx,
+            queue,
+        })
+    }
+
+    /// Adds music to be played once the current music has finished, the
+    /// music is returned if the thread has already finished playing.
+    pub fn queue(&self, music: Music) -> Result<(), Music> {
+        let mut queue = self.queue.lock().expect("queue");
+        if queue.finished {
+            return Err(music);
         }
+        queue.notes += music.notes();
+        queue.music.push_back(music);
+        Ok(())
+    }
+
+    /// Restarts the random variations of sound codes, and noise, from `seed`
+    /// for the next music in the queue, the music that's playing is unchanged
+    /// as it's scheduled in full before it starts.
+    pub fn set_seed(&self, seed: u64) {
+        self.queue.lock().expect("queue").seed = Some(seed);
+    }
+
+    /// Indicates whether anything waiting on the music should be held up,
+    /// which is always the case after `MF`, but only once the queue holds
+    /// 32 notes after `MB`.
+    pub fn is_blocking(&self) -> bool {
+        let queue = self.queue.lock().expect("queue");
+        !queue.finished && (!queue.background || queue.notes >= BACKGROUND_NOTES)
     }
 
     /// Indicates whether the [Player] has finished playing music and is
@@ -0,0 +1,19 @@
+use std::{ops::Range, time::Duration};
+
+/// Sent by a [Player](crate::Player) as each entity of [Music](crate::Music)
+/// starts, see [Player::subscribe](crate::Player::subscribe)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Progress {
+    /// The index of the entity in the music
+    pub index: usize,
+    /// Where the entity was found in the bytes the music was parsed from
+    pub range: Range<usize>,
+    /// The entity as it would be written
+    pub text: String,
+    /// The first frequency the entity plays, or [None] when it's silent
+    pub frequency: Option<f32>,
+    /// How long after the start of the music the entity starts
+    pub start: Duration,
+    /// How long the entity lasts, including any silence after it
+    pub duration: Duration,
+}
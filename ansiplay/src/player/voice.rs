@@ -0,0 +1,74 @@
+use crate::player::PcSpeaker;
+use basic_waves::{rodio::Source, NoiseWave, SawWave, SineWave, SquareWave, TriangleWave};
+
+/// The shape of the wave that the [Player](crate::Player) plays notes with
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Waveform {
+    #[default]
+    Square,
+    Sine,
+    Triangle,
+    Saw,
+    /// White noise, which ignores the frequency
+    Noise,
+    /// The IBM PC's speaker, see [PcSpeaker]
+    PcSpeaker,
+}
+
+impl Waveform {
+    pub const NAMES: [&'static str; 6] =
+        ["square", "sine", "triangle", "saw", "noise", "pc-speaker"];
+
+    /// Returns the waveform with one of the names in [Waveform::NAMES]
+    pub fn from_name(name: &str) -> Option<Waveform> {
+        match name {
+            "square" => Some(Waveform::Square),
+            "sine" => Some(Waveform::Sine),
+            "triangle" => Some(Waveform::Triangle),
+            "saw" => Some(Waveform::Saw),
+            "noise" => Some(Waveform::Noise),
+            "pc-speaker" => Some(Waveform::PcSpeaker),
+            _ => None,
+        }
+    }
+}
+
+/// How the [Player](crate::Player) sounds, its waveform and volume
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Voice {
+    pub waveform: Waveform,
+    /// From 0 to 1
+    pub volume: f32,
+}
+
+impl Default for Voice {
+    /// A square wave at a tenth of the full volume
+    fn default() -> Self {
+        Voice {
+            waveform: Waveform::Square,
+            volume: 0.1,
+        }
+    }
+}
+
+impl Voice {
+    /// Returns an infinite source playing `frequency`, `seed` is used by noise
+    pub(crate) fn source(
+        &self,
+        frequency: f32,
+        sample_rate: u32,
+        seed: u64,
+    ) -> Box<dyn Source<Item = f32> + Send> {
+        let volume = self.volume.clamp(0.0, 1.0);
+        match self.waveform {
+            Waveform::Square => Box::new(SquareWave::new(frequency, sample_rate).amplify(volume)),
+            Waveform::Sine => Box::new(SineWave::new(frequency, sample_rate).amplify(volume)),
+            Waveform::Triangle => {
+                Box::new(TriangleWave::new(frequency, sample_rate).amplify(volume))
+            }
+            Waveform::Saw => Box::new(SawWave::new(frequency, sample_rate).amplify(volume)),
+            Waveform::Noise => Box::new(NoiseWave::new(seed, sample_rate).amplify(volume)),
+            Waveform::PcSpeaker => Box::new(PcSpeaker::new(frequency, sample_rate).amplify(volume)),
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use crate::{
+    music::{Music, MusicEntity},
+    player::{Player, Tone},
+    renderer::RendererError,
+};
+use std::{fs, path::Path};
+
+const DIVISION: u16 = 480;
+const CHANNEL: u8 = 0;
+// Lead 1, the square wave in the General MIDI instruments
+const PROGRAM: u8 = 80;
+const VELOCITY: u8 = 100;
+// The index of the player's lowest note is two octaves below middle C
+const LOWEST_KEY: usize = 36;
+// The default range of the pitch wheel in semitones either side of the note
+const BEND_RANGE: f64 = 2.0;
+const BEND_CENTRE: u16 = 0x2000;
+
+/// Converts [Music] into a type 0 Standard MIDI File, following the timing of
+/// [Player], so that notes keep their articulation. Sound codes are
+/// approximated by the nearest key, bent to the frequency with the pitch wheel.
+#[derive(Debug)]
+pub struct MidiRenderer {
+    player: Player,
+    track: Vec<u8>,
+    // The time of the next event in ticks, and of the last written event
+    time: f64,
+    written: u64,
+    tempo: usize,
+}
+
+impl Default for MidiRenderer {
+    fn default() -> Self {
+        MidiRenderer::new()
+    }
+}
+
+impl MidiRenderer {
+    pub fn new() -> MidiRenderer {
+        MidiRenderer::with_player(Player::new())
+    }
+
+    /// Constructs a renderer which starts from the state of `player`
+    pub fn with_player(player: Player) -> MidiRenderer {
+        let mut midi = MidiRenderer {
+            tempo: player.tempo(),
+            player,
+            track: Vec::new(),
+            time: 0.0,
+            written: 0,
+        };
+        midi.set_tempo();
+        midi.event(&[0xc0 | CHANNEL, PROGRAM]);
+        midi
+    }
+
+    /// Appends `music` to the track, the player's state carries over from any
+    /// music rendered before
+    pub fn render(&mut self, music: Music) {
+        for entity in music {
+            let tempo_change = matches!(entity, MusicEntity::Tempo(_));
+            let tones = self.player.tones(entity);
+            if tempo_change && self.player.tempo() != self.tempo {
+                self.tempo = self.player.tempo();
+                self.set_tempo();
+            }
+            for tone in tones {
+                match tone {
+                    Tone::Note(index, ms) => self.note(LOWEST_KEY + index, None, ms),
+                    Tone::Play(frequency, ms) => match key_and_bend(frequency) {
+                        Some((key, bend)) => self.note(key, Some(bend), ms),
+                        None => self.rest(ms),
+                    },
+                    Tone::Pause(ms) | Tone::Wait(ms) => self.rest(ms),
+                }
+            }
+        }
+    }
+
+    /// Returns the contents of the MIDI file
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut track = self.track.clone();
+        let delta = (self.time.round() as u64).saturating_sub(self.written);
+        write_variable(&mut track, delta);
+        track.extend_from_slice(&[0xff, 0x2f, 0x00]);
+        let mut bytes = Vec::with_capacity(track.len() + 22);
+        bytes.extend_from_slice(b"MThd");
+        bytes.extend_from_slice(&6u32.to_be_bytes());
+        bytes.extend_from_slice(&0u16.to_be_bytes());
+        bytes.extend_from_slice(&1u16.to_be_bytes());
+        bytes.extend_from_slice(&DIVISION.to_be_bytes());
+        bytes.extend_from_slice(b"MTrk");
+        bytes.extend_from_slice(&(track.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&track);
+        bytes
+    }
+
+    pub fn write_midi(&self, path: impl AsRef<Path>) -> Result<(), RendererError> {
+        fs::write(path, self.to_bytes()).map_err(|_| RendererError::CannotWriteMidi)
+    }
+
+    // Writes an event at the current time
+    fn event(&mut self, event: &[u8]) {
+        let time = self.time.round() as u64;
+        write_variable(&mut self.track, time - self.written);
+        self.written = time;
+        self.track.extend_from_slice(event);
+    }
+
+    fn set_tempo(&mut self) {
+        let micros = (60_000_000 / self.tempo.max(1)).min(0xff_ffff) as u32;
+        let [_, a, b, c] = micros.to_be_bytes();
+        self.event(&[0xff, 0x51, 0x03, a, b, c]);
+    }
+
+    fn rest(&mut self, ms: f64) {
+        // A quarter note lasts 60,000ms divided by the tempo
+        self.time += ms * self.tempo as f64 * DIVISION as f64 / 60_000.0;
+    }
+
+    fn note(&mut self, key: usize, bend: Option<u16>, ms: f64) {
+        if ms <= 0.0 {
+            return;
+        }
+        let key = key.min(127) as u8;
+        if let Some(bend) = bend {
+            self.bend(bend);
+        }
+        self.event(&[0x90 | CHANNEL, key, VELOCITY]);
+        self.rest(ms);
+        self.event(&[0x80 | CHANNEL, key, 0]);
+        if bend.is_some() {
+            self.bend(BEND_CENTRE);
+        }
+    }
+
+    fn bend(&mut self, value: u16) {
+        self.event(&[0xe0 | CHANNEL, (value & 0x7f) as u8, (value >> 7) as u8]);
+    }
+}
+
+// Finds the nearest key to a frequency, and the value of the pitch wheel that
+// makes up the difference, returns `None` for frequencies outside of MIDI's
+// range
+fn key_and_bend(frequency: f32) -> Option<(usize, u16)> {
+    let semitones = 69.0 + 12.0 * (frequency as f64 / 440.0).log2();
+    if !(0.0..=127.0).contains(&semitones) {
+        return None;
+    }
+    let key = semitones.round();
+    let bend = BEND_CENTRE as f64 + (semitones - key) / BEND_RANGE * BEND_CENTRE as f64;
+    Some((key as usize, bend.round().clamp(0.0, 0x3fff as f64) as u16))
+}
+
+// Writes a MIDI variable length quantity, seven bits at a time with the
+// highest bit set on all but the last byte
+fn write_variable(bytes: &mut Vec<u8>, value: u64) {
+    let mut groups = vec![(value & 0x7f) as u8];
+    let mut value = value >> 7;
+    while value > 0 {
+        groups.push((value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.extend(groups.iter().rev());
+}
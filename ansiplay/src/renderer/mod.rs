@@ -0,0 +1,81 @@
+mod midi;
+mod renderer_error;
+use crate::{
+    music::Music,
+    player::{MusicSource, Player, SAMPLE_RATE},
+};
+pub use midi::MidiRenderer;
+pub use renderer_error::RendererError;
+use std::{path::Path, time::Duration};
+
+/// Renders [Music] as mono PCM samples without an audio device, following
+/// the same timing as [Player], so that music can be saved as a WAV file.
+#[derive(Debug)]
+pub struct Renderer {
+    player: Player,
+    sample_rate: u32,
+    samples: Vec<f32>,
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Renderer::new(SAMPLE_RATE)
+    }
+}
+
+impl Renderer {
+    /// Constructs a renderer with a fresh [Player] at `sample_rate`
+    pub fn new(sample_rate: u32) -> Renderer {
+        Renderer::with_player(Player::new(), sample_rate)
+    }
+
+    /// Constructs a renderer which starts from the state of `player`
+    pub fn with_player(player: Player, sample_rate: u32) -> Renderer {
+        Renderer {
+            player,
+            sample_rate: sample_rate.max(1),
+            samples: Vec::new(),
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn samples(&self) -> &[f32] {
+        &self.samples
+    }
+
+    /// Returns the length of the music rendered so far
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.samples.len() as f64 / self.sample_rate as f64)
+    }
+
+    /// Appends `music` to the samples, the player's tempo, octave, and so on
+    /// carry over from any music rendered before, as they would for a
+    /// sequence of pieces in an ANSI file. A sound code's delay is rendered
+    /// as silence, which is how long a terminal waits before carrying on.
+    pub fn render(&mut self, music: Music) {
+        let source = MusicSource::new(&mut self.player, music.into_spans(), self.sample_rate, true);
+        self.samples.extend(source);
+    }
+
+    /// Writes the samples as a 16 bit mono WAV file
+    pub fn write_wav(&self, path: impl AsRef<Path>) -> Result<(), RendererError> {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: self.sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer =
+            hound::WavWriter::create(path, spec).map_err(|_| RendererError::CannotWriteWav)?;
+        for sample in &self.samples {
+            let sample = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+            writer
+                .write_sample(sample)
+                .map_err(|_| RendererError::CannotWriteWav)?;
+        }
+        writer.finalize().map_err(|_| RendererError::CannotWriteWav)
+    }
+}
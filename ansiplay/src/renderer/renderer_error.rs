@@ -0,0 +1,19 @@
+use std::{error, fmt};
+
+/// Custom error used when writing the output of a [crate::Renderer]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RendererError {
+    CannotWriteWav,
+    CannotWriteMidi,
+}
+
+impl fmt::Display for RendererError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RendererError::CannotWriteWav => write!(f, "Cannot write WAV file"),
+            RendererError::CannotWriteMidi => write!(f, "Cannot write MIDI file"),
+        }
+    }
+}
+
+impl error::Error for RendererError {}
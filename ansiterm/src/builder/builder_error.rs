@@ -0,0 +1,19 @@
+use std::{error, fmt};
+
+/// Error produced when building a terminal window
+#[derive(Debug)]
+pub enum BuilderError {
+    CannotCreateWindow,
+    CannotCreateSurface,
+}
+
+impl fmt::Display for BuilderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuilderError::CannotCreateWindow => write!(f, "Cannot create window"),
+            BuilderError::CannotCreateSurface => write!(f, "Cannot create surface"),
+        }
+    }
+}
+
+impl error::Error for BuilderError {}
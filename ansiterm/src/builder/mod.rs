@@ -0,0 +1,287 @@
+mod builder_error;
+use crate::{
+    capture::Capture,
+    clipboard::{Clipboard, SystemClipboard},
+    crt::{CrtFilter, CrtSettings},
+    scaler::Scaler,
+    terminal::{CursorStyle, Palette, Profile, Terminal},
+    window::{window_size, TerminalMessage, TerminalWindow},
+    BORDER_SIZE,
+};
+use ansiart::{
+    codepage437::Font,
+    ega_palette::{Rgba, BLACK_RGBA},
+};
+pub use builder_error::BuilderError;
+use pixels::{wgpu::Color, PixelsBuilder, SurfaceTexture};
+use std::path::PathBuf;
+use winit::{dpi::LogicalSize, event_loop::EventLoop, window::WindowBuilder};
+
+/// Configures and constructs a [TerminalWindow], `T` is the type of the
+/// host's own events, which can be set with [TerminalBuilder::event_loop]
+pub struct TerminalBuilder<T: 'static = ()> {
+    columns: usize,
+    rows: usize,
+    scale: usize,
+    ice_colors: bool,
+    aspect_correction: bool,
+    crt: Option<CrtSettings>,
+    title: String,
+    font: Font,
+    palette: Palette,
+    profile: Profile,
+    border: Rgba,
+    cursor_style: CursorStyle,
+    cursor_blink: bool,
+    audio: bool,
+    status_line: bool,
+    playback_controls: bool,
+    capture_dir: PathBuf,
+    clipboard: Box<dyn Clipboard>,
+    event_loop: Option<EventLoop<TerminalMessage<T>>>,
+}
+
+impl Default for TerminalBuilder {
+    /// An 80x25 terminal at double size, with the default font and ANSI palette
+    fn default() -> Self {
+        TerminalBuilder {
+            columns: 80,
+            rows: 25,
+            scale: 2,
+            ice_colors: false,
+            aspect_correction: false,
+            crt: None,
+            title: String::from("ANSI Art"),
+            font: Font::default(),
+            palette: Palette::default(),
+            profile: Profile::default(),
+            border: BLACK_RGBA,
+            cursor_style: CursorStyle::default(),
+            cursor_blink: true,
+            audio: true,
+            status_line: false,
+            playback_controls: true,
+            capture_dir: PathBuf::from("."),
+            clipboard: Box::new(SystemClipboard),
+            event_loop: None,
+        }
+    }
+}
+
+impl TerminalBuilder {
+    pub fn new() -> TerminalBuilder {
+        TerminalBuilder::default()
+    }
+}
+
+impl<T: 'static> TerminalBuilder<T> {
+    /// Sets the dimensions of the terminal in columns and rows
+    pub fn size(mut self, columns: usize, rows: usize) -> Self {
+        self.columns = columns;
+        self.rows = rows;
+        self
+    }
+
+    /// Sets the initial scale of the window
+    pub fn scale(mut self, scale: usize) -> Self {
+        self.scale = scale.max(1);
+        self
+    }
+
+    /// Uses the blink attribute to select bright background colours
+    pub fn ice_colors(mut self, ice_colors: bool) -> Self {
+        self.ice_colors = ice_colors;
+        self
+    }
+
+    /// Stretches the display to the aspect ratio of a 4:3 display
+    pub fn aspect_correction(mut self, aspect_correction: bool) -> Self {
+        self.aspect_correction = aspect_correction;
+        self
+    }
+
+    /// Simulates a CRT display
+    pub fn crt(mut self, crt: Option<CrtSettings>) -> Self {
+        self.crt = crt;
+        self
+    }
+
+    /// Sets the title of the window
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Sets the font the terminal draws with
+    pub fn font(mut self, font: Font) -> Self {
+        self.font = font;
+        self
+    }
+
+    /// Sets the palette
+    pub fn palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    /// Follows the conventions of another computer, which also sets the font,
+    /// palette, and number of columns, these can be changed afterwards
+    pub fn profile(mut self, profile: Profile) -> Self {
+        self.profile = profile;
+        self.font = profile.font();
+        self.palette = profile.palette();
+        self.columns = profile.columns();
+        self
+    }
+
+    /// Sets the colour of the border around the terminal
+    pub fn border(mut self, border: Rgba) -> Self {
+        self.border = border;
+        self
+    }
+
+    /// Sets the shape of the cursor
+    pub fn cursor_style(mut self, cursor_style: CursorStyle) -> Self {
+        self.cursor_style = cursor_style;
+        self
+    }
+
+    /// Sets whether the cursor blinks
+    pub fn cursor_blink(mut self, cursor_blink: bool) -> Self {
+        self.cursor_blink = cursor_blink;
+        self
+    }
+
+    /// Enables or disables the sound of ANSI Music, when disabled, or when
+    /// there is no audio device, music is still timed but plays silently
+    pub fn audio(mut self, audio: bool) -> Self {
+        self.audio = audio;
+        self
+    }
+
+    /// Shows the note that's playing, and where it was in the music, on the
+    /// bottom line of the terminal whilst ANSI Music plays
+    pub fn status_line(mut self, status_line: bool) -> Self {
+        self.status_line = status_line;
+        self
+    }
+
+    /// Enables the keys which control playback, space pauses, + and - step
+    /// the baud rate, right skips to the end, and R restarts. When disabled,
+    /// as when the input isn't a file, the keys are passed to the event loop.
+    pub fn playback_controls(mut self, playback_controls: bool) -> Self {
+        self.playback_controls = playback_controls;
+        self
+    }
+
+    /// Sets the directory screenshots (F12) and recordings (F11) are written to,
+    /// both capture the display as shown, with the selection and status line
+    /// but without the CRT filter
+    pub fn capture_dir(mut self, capture_dir: impl Into<PathBuf>) -> Self {
+        self.capture_dir = capture_dir.into();
+        self
+    }
+
+    /// Sets where selections are copied to, which is the system clipboard by
+    /// default
+    pub fn clipboard(mut self, clipboard: impl Clipboard + 'static) -> Self {
+        self.clipboard = Box::new(clipboard);
+        self
+    }
+
+    /// Uses an existing event loop, so that proxies can be created before the
+    /// window is built. The loop's events can carry the host's own events,
+    /// which are passed to the window's event loop as
+    /// [TerminalEvent::User](crate::TerminalEvent::User)
+    pub fn event_loop<U: 'static>(
+        self,
+        event_loop: EventLoop<TerminalMessage<U>>,
+    ) -> TerminalBuilder<U> {
+        TerminalBuilder {
+            columns: self.columns,
+            rows: self.rows,
+            scale: self.scale,
+            ice_colors: self.ice_colors,
+            aspect_correction: self.aspect_correction,
+            crt: self.crt,
+            title: self.title,
+            font: self.font,
+            palette: self.palette,
+            profile: self.profile,
+            border: self.border,
+            cursor_style: self.cursor_style,
+            cursor_blink: self.cursor_blink,
+            audio: self.audio,
+            status_line: self.status_line,
+            playback_controls: self.playback_controls,
+            capture_dir: self.capture_dir,
+            clipboard: self.clipboard,
+            event_loop: Some(event_loop),
+        }
+    }
+
+    /// Constructs the window, which is displayed once
+    /// [TerminalWindow::run] is called
+    pub fn build(self) -> Result<TerminalWindow<T>, BuilderError> {
+        let mut term = Terminal::with_appearance(
+            self.columns,
+            self.rows,
+            self.ice_colors,
+            self.font,
+            self.palette,
+            self.cursor_style,
+        );
+        term.set_profile(self.profile);
+        term.set_cursor_blink(self.cursor_blink);
+        let (width, height) = term.get_dimensions();
+        let crt = self
+            .crt
+            .map(|settings| CrtFilter::new(width, height, settings));
+        let (filtered_width, filtered_height) = match crt {
+            Some(ref crt) => crt.output_dimensions(),
+            None => (width, height),
+        };
+        let mut scaler = Scaler::new(filtered_width, filtered_height, self.aspect_correction);
+        let event_loop = match self.event_loop {
+            Some(event_loop) => event_loop,
+            None => EventLoop::with_user_event(),
+        };
+        let min_size = window_size(filtered_width, filtered_height, 1, self.aspect_correction);
+        let size = window_size(width, height, self.scale as u32, self.aspect_correction);
+        let window = WindowBuilder::new()
+            .with_inner_size(LogicalSize::new(
+                size.width.max(min_size.width),
+                size.height.max(min_size.height),
+            ))
+            .with_min_inner_size(min_size)
+            .with_title(self.title)
+            .build(&event_loop)
+            .map_err(|_| BuilderError::CannotCreateWindow)?;
+        let pixels = {
+            let window_size = window.inner_size();
+            let border = (BORDER_SIZE as f64 * window.scale_factor()).round() as u32;
+            scaler.resize(window_size.width, window_size.height, border);
+            let (output_width, output_height) = scaler.output_dimensions();
+            let surface_texture =
+                SurfaceTexture::new(window_size.width, window_size.height, &window);
+            let [r, g, b, _] = self.border.map(|value| value as f64 / 255.0);
+            PixelsBuilder::new(output_width, output_height, surface_texture)
+                .clear_color(Color { r, g, b, a: 1.0 })
+                .build()
+                .map_err(|_| BuilderError::CannotCreateSurface)?
+        };
+        Ok(TerminalWindow {
+            event_loop,
+            window,
+            pixels,
+            term,
+            scaler,
+            crt,
+            capture: Capture::new(self.capture_dir, width, height),
+            audio: self.audio,
+            status_line: self.status_line,
+            playback_controls: self.playback_controls,
+            clipboard: self.clipboard,
+        })
+    }
+}
@@ -0,0 +1,21 @@
+use std::{error, fmt};
+
+/// Error produced when writing screenshots or recordings
+#[derive(Debug)]
+pub enum CaptureError {
+    CannotWriteImage,
+    CannotWriteRecording,
+    ThreadError,
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaptureError::CannotWriteImage => write!(f, "Cannot write image"),
+            CaptureError::CannotWriteRecording => write!(f, "Cannot write recording"),
+            CaptureError::ThreadError => write!(f, "Thread Error"),
+        }
+    }
+}
+
+impl error::Error for CaptureError {}
@@ -0,0 +1,74 @@
+mod capture_error;
+mod recorder;
+pub use capture_error::CaptureError;
+use chrono::Local;
+use image::RgbaImage;
+use recorder::Recorder;
+use std::path::{Path, PathBuf};
+
+/// Writes screenshots and recordings of the terminal's frames to a directory,
+/// each named with the time they were started.
+pub struct Capture {
+    directory: PathBuf,
+    width: u32,
+    height: u32,
+    recorder: Option<Recorder>,
+}
+
+impl Capture {
+    pub fn new(directory: impl AsRef<Path>, width: u32, height: u32) -> Capture {
+        Capture {
+            directory: directory.as_ref().to_owned(),
+            width,
+            height,
+            recorder: None,
+        }
+    }
+
+    fn timestamped_path(&self, extension: &str) -> PathBuf {
+        let timestamp = Local::now().format("%Y%m%d-%H%M%S-%3f");
+        self.directory
+            .join(format!("ansiterm-{timestamp}.{extension}"))
+    }
+
+    /// Writes the frame to a PNG file and returns its path.
+    pub fn screenshot(&self, frame: &[u8]) -> Result<PathBuf, CaptureError> {
+        let path = self.timestamped_path("png");
+        match RgbaImage::from_raw(self.width, self.height, frame.to_vec()) {
+            Some(image) => match image.save(&path) {
+                Ok(()) => Ok(path),
+                Err(_err) => Err(CaptureError::CannotWriteImage),
+            },
+            None => Err(CaptureError::CannotWriteImage),
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    /// Starts recording to a new animated GIF, or finishes the current
+    /// recording, returning the path of the file in both cases.
+    pub fn toggle_recording(&mut self) -> Result<PathBuf, CaptureError> {
+        match self.stop_recording() {
+            Some(result) => result,
+            None => {
+                let path = self.timestamped_path("gif");
+                self.recorder = Some(Recorder::new(&path, self.width, self.height)?);
+                Ok(path)
+            }
+        }
+    }
+
+    /// Finishes the current recording, if there is one.
+    pub fn stop_recording(&mut self) -> Option<Result<PathBuf, CaptureError>> {
+        self.recorder.take().map(Recorder::finish)
+    }
+
+    /// Adds the frame to the current recording, if there is one.
+    pub fn record(&mut self, frame: &[u8]) {
+        if let Some(ref mut recorder) = self.recorder {
+            recorder.record(frame);
+        }
+    }
+}
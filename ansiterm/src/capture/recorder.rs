@@ -0,0 +1,140 @@
+use crate::capture::CaptureError;
+use image::{
+    codecs::gif::{GifEncoder, Repeat},
+    Delay, Frame, RgbaImage,
+};
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, SyncSender, TrySendError},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// The number of frames that can wait to be encoded, frames that arrive
+/// whilst the encoder is this far behind are dropped
+const QUEUED_FRAMES: usize = 8;
+
+/// Encodes frames to an animated GIF in a separate thread, only frames that
+/// differ from the previous one are sent, along with how long the previous
+/// frame was shown for.
+pub struct Recorder {
+    path: PathBuf,
+    tx: SyncSender<(Vec<u8>, Delay)>,
+    handle: JoinHandle<Result<(), CaptureError>>,
+    last_frame: Option<(Vec<u8>, Instant)>,
+    // How long dropped frames were shown for, added to the next frame sent
+    dropped: Duration,
+}
+
+fn encode(
+    file: File,
+    width: u32,
+    height: u32,
+    rx: mpsc::Receiver<(Vec<u8>, Delay)>,
+) -> Result<(), CaptureError> {
+    let mut encoder = GifEncoder::new(file);
+    if encoder.set_repeat(Repeat::Infinite).is_err() {
+        return Err(CaptureError::CannotWriteRecording);
+    }
+    for (bytes, delay) in rx {
+        match RgbaImage::from_raw(width, height, bytes) {
+            Some(image) => {
+                if encoder
+                    .encode_frame(Frame::from_parts(image, 0, 0, delay))
+                    .is_err()
+                {
+                    return Err(CaptureError::CannotWriteRecording);
+                }
+            }
+            None => return Err(CaptureError::CannotWriteRecording),
+        }
+    }
+    Ok(())
+}
+
+impl Recorder {
+    pub fn new(path: &Path, width: u32, height: u32) -> Result<Recorder, CaptureError> {
+        match File::create(path) {
+            Ok(file) => {
+                let (tx, rx) = mpsc::sync_channel(QUEUED_FRAMES);
+                let handle = thread::spawn(move || encode(file, width, height, rx));
+                Ok(Recorder {
+                    path: path.to_owned(),
+                    tx,
+                    handle,
+                    last_frame: None,
+                    dropped: Duration::ZERO,
+                })
+            }
+            Err(_err) => Err(CaptureError::CannotWriteRecording),
+        }
+    }
+
+    // Sends the previous frame, unless the encoder has fallen behind and
+    // `wait` is false
+    fn send_last_frame(&mut self, wait: bool) {
+        if let Some((bytes, instant)) = self.last_frame.take() {
+            let shown = instant.elapsed() + self.dropped;
+            let frame = (bytes, Delay::from_saturating_duration(shown));
+            self.dropped = if wait {
+                self.tx.send(frame).ok();
+                Duration::ZERO
+            } else {
+                match self.tx.try_send(frame) {
+                    Err(TrySendError::Full(_)) => shown,
+                    _ => Duration::ZERO,
+                }
+            };
+        }
+    }
+
+    pub fn record(&mut self, frame: &[u8]) {
+        match self.last_frame {
+            Some((ref bytes, _)) if bytes == frame => {}
+            _ => {
+                self.send_last_frame(false);
+                self.last_frame = Some((frame.to_vec(), Instant::now()));
+            }
+        }
+    }
+
+    /// Sends the final frame and waits for the encoding to complete.
+    pub fn finish(mut self) -> Result<PathBuf, CaptureError> {
+        self.send_last_frame(true);
+        drop(self.tx);
+        match self.handle.join() {
+            Ok(Ok(())) => Ok(self.path),
+            Ok(Err(err)) => Err(err),
+            Err(_) => Err(CaptureError::ThreadError),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Recorder;
+    use image::{codecs::gif::GifDecoder, AnimationDecoder};
+    use std::{fs::File, io::BufReader};
+
+    #[test]
+    fn encodes_changed_frames() {
+        let path = std::env::temp_dir().join("ansiterm-recorder-test.gif");
+        let mut recorder = Recorder::new(&path, 4, 2).expect("recorder");
+        let frames = [[0; 32], [255; 32], [255; 32], [0; 32]];
+        for frame in frames.iter() {
+            recorder.record(frame);
+        }
+        assert_eq!(recorder.finish().expect("finish"), path);
+        let file = BufReader::new(File::open(&path).expect("gif"));
+        let decoded = GifDecoder::new(file)
+            .expect("decoder")
+            .into_frames()
+            .collect_frames()
+            .expect("frames");
+        std::fs::remove_file(&path).ok();
+        // The repeated frame is only encoded once
+        assert_eq!(decoded.len(), 3);
+        assert_eq!(decoded[0].buffer().dimensions(), (4, 2));
+    }
+}
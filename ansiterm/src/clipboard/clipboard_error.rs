@@ -0,0 +1,19 @@
+use std::{error, fmt};
+
+/// Error produced when copying to the clipboard
+#[derive(Debug)]
+pub enum ClipboardError {
+    NoClipboardCommand,
+    CannotWriteToClipboard,
+}
+
+impl fmt::Display for ClipboardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClipboardError::NoClipboardCommand => write!(f, "No clipboard command available"),
+            ClipboardError::CannotWriteToClipboard => write!(f, "Cannot write to clipboard"),
+        }
+    }
+}
+
+impl error::Error for ClipboardError {}
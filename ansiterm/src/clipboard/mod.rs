@@ -0,0 +1,78 @@
+mod clipboard_error;
+pub use clipboard_error::ClipboardError;
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// Somewhere that copied text can be sent
+pub trait Clipboard {
+    fn set_text(&mut self, text: String) -> Result<(), ClipboardError>;
+}
+
+/// A clipboard which keeps the copied text in memory, for when the system
+/// clipboard isn't wanted or available
+#[derive(Clone, Debug, Default)]
+pub struct LocalClipboard {
+    text: Option<String>,
+}
+
+impl LocalClipboard {
+    pub fn new() -> LocalClipboard {
+        LocalClipboard::default()
+    }
+
+    /// Returns the text most recently copied
+    pub fn text(&self) -> Option<&str> {
+        self.text.as_deref()
+    }
+}
+
+impl Clipboard for LocalClipboard {
+    fn set_text(&mut self, text: String) -> Result<(), ClipboardError> {
+        self.text = Some(text);
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "macos")]
+const COMMANDS: &[(&str, &[&str])] = &[("pbcopy", &[])];
+#[cfg(target_os = "windows")]
+const COMMANDS: &[(&str, &[&str])] = &[("clip", &[])];
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const COMMANDS: &[(&str, &[&str])] = &[
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+];
+
+/// The system clipboard, written to by piping text to the platform's
+/// clipboard command (pbcopy, clip, wl-copy, xclip, or xsel)
+#[derive(Clone, Debug, Default)]
+pub struct SystemClipboard;
+
+impl Clipboard for SystemClipboard {
+    fn set_text(&mut self, text: String) -> Result<(), ClipboardError> {
+        for (command, args) in COMMANDS {
+            let mut child = match Command::new(command)
+                .args(*args)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()
+            {
+                Ok(child) => child,
+                Err(_) => continue,
+            };
+            let written = match child.stdin.take() {
+                Some(mut stdin) => stdin.write_all(text.as_bytes()).is_ok(),
+                None => false,
+            };
+            return match child.wait() {
+                Ok(status) if written && status.success() => Ok(()),
+                _ => Err(ClipboardError::CannotWriteToClipboard),
+            };
+        }
+        Err(ClipboardError::NoClipboardCommand)
+    }
+}
@@ -0,0 +1,223 @@
+use std::time::{Duration, Instant};
+
+/// The frame rate persistence is measured against, how much of the previous
+/// frame remains after a 60th of a second
+const PERSISTENCE_INTERVAL: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+/// Settings for the CRT filter, each value ranges from 0.0 (off) to 1.0
+#[derive(Clone, Debug)]
+pub struct CrtSettings {
+    /// How much darker the gaps between scanlines are
+    pub scanlines: f32,
+    /// How much each pixel bleeds into its horizontal neighbours
+    pub bloom: f32,
+    /// How much of the previous frame remains after a 60th of a second as
+    /// the phosphor fades
+    pub persistence: f32,
+    /// How much the screen bulges towards the viewer
+    pub curvature: f32,
+}
+
+impl Default for CrtSettings {
+    fn default() -> Self {
+        CrtSettings {
+            scanlines: 0.5,
+            bloom: 0.25,
+            persistence: 0.5,
+            curvature: 0.1,
+        }
+    }
+}
+
+/// Simulates a CRT display on the CPU, producing a frame twice the width and
+/// height of the terminal's so that scanlines and curvature can be drawn.
+pub struct CrtFilter {
+    settings: CrtSettings,
+    width: usize,
+    height: usize,
+    curvature: f32,
+    phosphor: Vec<u8>,
+    bloomed: Vec<u8>,
+    last_draw: Option<Instant>,
+    // For every output pixel, the index of the source pixel and whether it
+    // falls between scanlines, or `None` when it lies beyond the curved edge
+    samples: Vec<Option<(usize, bool)>>,
+}
+
+impl CrtFilter {
+    pub fn new(width: u32, height: u32, settings: CrtSettings) -> CrtFilter {
+        let (width, height) = (width as usize, height as usize);
+        let curvature = settings.curvature.clamp(0.0, 1.0) * 0.25;
+        let mut filter = CrtFilter {
+            settings,
+            width,
+            height,
+            curvature,
+            phosphor: vec![0; width * height * 4],
+            bloomed: vec![0; width * height * 4],
+            last_draw: None,
+            samples: Vec::new(),
+        };
+        let (output_width, output_height) = (width * 2, height * 2);
+        filter.samples = (0..output_height)
+            .flat_map(|y| (0..output_width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                filter.curve(x, y).map(|(sample_x, sample_y)| {
+                    ((sample_y / 2) * width + sample_x / 2, sample_y % 2 == 1)
+                })
+            })
+            .collect();
+        filter
+    }
+
+    // Bends a pixel of the output through the curve of the screen, returning
+    // the position in the output it shows, which may lie beyond the edges
+    fn distort(&self, x: usize, y: usize) -> (f32, f32) {
+        let (output_width, output_height) = (self.width as f32 * 2.0, self.height as f32 * 2.0);
+        let u = (x as f32 + 0.5) / output_width * 2.0 - 1.0;
+        let v = (y as f32 + 0.5) / output_height * 2.0 - 1.0;
+        let distortion = 1.0 + self.curvature * (u * u + v * v);
+        (
+            ((u * distortion + 1.0) / 2.0 * output_width).floor(),
+            ((v * distortion + 1.0) / 2.0 * output_height).floor(),
+        )
+    }
+
+    // Returns the pixel of the input shown at a pixel of the output, or
+    // `None` beyond the curved edge
+    fn curve(&self, x: usize, y: usize) -> Option<(usize, usize)> {
+        let (sample_x, sample_y) = self.distort(x, y);
+        if sample_x < 0.0
+            || sample_y < 0.0
+            || sample_x >= self.width as f32 * 2.0
+            || sample_y >= self.height as f32 * 2.0
+        {
+            None
+        } else {
+            Some((sample_x as usize, sample_y as usize))
+        }
+    }
+
+    /// Maps a position in the filtered frame back to the position in the
+    /// terminal's frame that's drawn there, positions beyond the curved edge
+    /// are clamped to the nearest edge of the terminal
+    pub fn to_frame_position(&self, x: usize, y: usize) -> (usize, usize) {
+        let (sample_x, sample_y) = self.distort(x, y);
+        (
+            (sample_x.max(0.0) as usize / 2).min(self.width - 1),
+            (sample_y.max(0.0) as usize / 2).min(self.height - 1),
+        )
+    }
+
+    /// Returns the dimensions of the filtered frame
+    pub fn output_dimensions(&self) -> (u32, u32) {
+        (self.width as u32 * 2, self.height as u32 * 2)
+    }
+
+    /// Draws the filtered RGBA `frame` into `output`, which should have the
+    /// dimensions given by `output_dimensions()`
+    pub fn draw(&mut self, frame: &[u8], output: &mut [u8]) {
+        let now = Instant::now();
+        let elapsed = self
+            .last_draw
+            .map_or(PERSISTENCE_INTERVAL, |last_draw| now - last_draw);
+        self.last_draw = Some(now);
+        self.draw_after(frame, output, elapsed);
+    }
+
+    // Draws a frame which follows the previous by `elapsed`, so the phosphor
+    // fades at the same rate whatever the frame rate
+    fn draw_after(&mut self, frame: &[u8], output: &mut [u8], elapsed: Duration) {
+        let persistence = self
+            .settings
+            .persistence
+            .clamp(0.0, 1.0)
+            .powf(elapsed.as_secs_f32() / PERSISTENCE_INTERVAL.as_secs_f32());
+        for (phosphor, value) in self.phosphor.iter_mut().zip(frame.iter()) {
+            let faded = (*phosphor as f32 * persistence) as u8;
+            *phosphor = faded.max(*value);
+        }
+        let bloom = self.settings.bloom.clamp(0.0, 1.0) / 2.0;
+        let stride = self.width * 4;
+        for (bloomed_row, row) in self
+            .bloomed
+            .chunks_exact_mut(stride)
+            .zip(self.phosphor.chunks_exact(stride))
+        {
+            for (i, bloomed) in bloomed_row.iter_mut().enumerate() {
+                let left = if i >= 4 { row[i - 4] } else { 0 };
+                let right = if i + 4 < stride { row[i + 4] } else { 0 };
+                let value = row[i] as f32 + bloom * (left as f32 + right as f32);
+                *bloomed = value.min(255.0) as u8;
+            }
+        }
+        let gap = 1.0 - self.settings.scanlines.clamp(0.0, 1.0);
+        for (pixel, sample) in output.chunks_exact_mut(4).zip(self.samples.iter()) {
+            match sample {
+                Some((index, between_scanlines)) => {
+                    let source = &self.bloomed[index * 4..index * 4 + 4];
+                    if *between_scanlines {
+                        for (dest, value) in pixel.iter_mut().zip(source.iter()).take(3) {
+                            *dest = (*value as f32 * gap) as u8;
+                        }
+                        pixel[3] = 255;
+                    } else {
+                        pixel.copy_from_slice(source);
+                    }
+                }
+                None => pixel.copy_from_slice(&[0, 0, 0, 255]),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn settings(persistence: f32, curvature: f32) -> CrtSettings {
+        CrtSettings {
+            scanlines: 0.0,
+            bloom: 0.0,
+            persistence,
+            curvature,
+        }
+    }
+
+    #[test]
+    fn map_positions_through_the_curve() {
+        let flat = CrtFilter::new(80, 50, settings(0.0, 0.0));
+        assert_eq!(flat.to_frame_position(0, 0), (0, 0));
+        assert_eq!(flat.to_frame_position(101, 33), (50, 16));
+        assert_eq!(flat.to_frame_position(159, 99), (79, 49));
+        let mut curved = CrtFilter::new(80, 50, settings(0.0, 1.0));
+        assert_eq!(curved.to_frame_position(80, 50), (40, 25));
+        // Near the edges the curve pulls positions outwards
+        let (x, y) = curved.to_frame_position(10, 10);
+        assert!(x < 5 && y < 5);
+        // Beyond the curved edge the nearest cell is used
+        assert_eq!(curved.to_frame_position(0, 0), (0, 0));
+        assert_eq!(curved.to_frame_position(159, 99), (79, 49));
+        // The mapping agrees with what the filter draws
+        let mut frame = vec![0; 80 * 50 * 4];
+        let (x, y) = curved.to_frame_position(120, 70);
+        frame[(y * 80 + x) * 4..(y * 80 + x) * 4 + 4].copy_from_slice(&[255; 4]);
+        let mut output = vec![0; 160 * 100 * 4];
+        curved.draw(&frame, &mut output);
+        assert_eq!(output[(70 * 160 + 120) * 4], 255);
+    }
+
+    #[test]
+    fn persistence_follows_time() {
+        let mut filter = CrtFilter::new(1, 1, settings(0.5, 0.0));
+        let mut output = vec![0; 2 * 2 * 4];
+        filter.draw_after(&[255; 4], &mut output, PERSISTENCE_INTERVAL);
+        assert_eq!(output[0], 255);
+        filter.draw_after(&[0; 4], &mut output, PERSISTENCE_INTERVAL);
+        assert_eq!(output[0], 127);
+        // Two frames' time fades as much as two frames
+        filter.draw_after(&[255; 4], &mut output, PERSISTENCE_INTERVAL);
+        filter.draw_after(&[0; 4], &mut output, PERSISTENCE_INTERVAL * 2);
+        assert_eq!(output[0], 63);
+    }
+}
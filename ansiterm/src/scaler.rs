@@ -0,0 +1,131 @@
+// VGA text modes have 400 scanlines, which were stretched to fill a 4:3 display
+pub const ASPECT_CORRECTION: f64 = 1.2;
+
+/// Fits the terminal's frame into the window. By default the frame is passed
+/// straight through and left to be scaled by the largest integer that fits,
+/// otherwise it is resampled to fill the window with the aspect ratio of a
+/// 4:3 display.
+pub struct Scaler {
+    width: u32,
+    height: u32,
+    aspect_correction: bool,
+    output_width: u32,
+    output_height: u32,
+}
+
+impl Scaler {
+    pub fn new(width: u32, height: u32, aspect_correction: bool) -> Scaler {
+        Scaler {
+            width,
+            height,
+            aspect_correction,
+            output_width: width,
+            output_height: height,
+        }
+    }
+
+    /// Returns the dimensions of the buffer the scaled frame is drawn into
+    pub fn output_dimensions(&self) -> (u32, u32) {
+        (self.output_width, self.output_height)
+    }
+
+    /// Recalculates the dimensions of the output for a new surface size, and
+    /// returns `true` if they have changed
+    pub fn resize(&mut self, surface_width: u32, surface_height: u32, border: u32) -> bool {
+        if !self.aspect_correction {
+            return false;
+        }
+        let width = surface_width.saturating_sub(border * 2).max(1) as f64;
+        let height = surface_height.saturating_sub(border * 2).max(1) as f64;
+        let aspect = self.width as f64 / (self.height as f64 * ASPECT_CORRECTION);
+        let (output_width, output_height) = if width / height > aspect {
+            ((height * aspect).round(), height)
+        } else {
+            (width, (width / aspect).round())
+        };
+        let (output_width, output_height) =
+            ((output_width as u32).max(1), (output_height as u32).max(1));
+        if (output_width, output_height) == (self.output_width, self.output_height) {
+            return false;
+        }
+        self.output_width = output_width;
+        self.output_height = output_height;
+        true
+    }
+
+    /// Maps a position in the output back to a position in the frame
+    pub fn to_frame_position(&self, x: usize, y: usize) -> (usize, usize) {
+        (
+            x * self.width as usize / self.output_width as usize,
+            y * self.height as usize / self.output_height as usize,
+        )
+    }
+
+    /// Draws the RGBA `frame` into `output`, which should have the dimensions
+    /// given by `output_dimensions()`
+    pub fn draw(&self, frame: &[u8], output: &mut [u8]) {
+        if (self.output_width, self.output_height) == (self.width, self.height) {
+            output.copy_from_slice(frame);
+            return;
+        }
+        let columns: Vec<usize> = (0..self.output_width as usize)
+            .map(|x| self.to_frame_position(x, 0).0 * 4)
+            .collect();
+        let frame_stride = self.width as usize * 4;
+        for (y, row) in output
+            .chunks_exact_mut(self.output_width as usize * 4)
+            .enumerate()
+        {
+            let (_, frame_y) = self.to_frame_position(0, y);
+            let frame_row = &frame[frame_y * frame_stride..(frame_y + 1) * frame_stride];
+            for (pixel, i) in row.chunks_exact_mut(4).zip(columns.iter()) {
+                pixel.copy_from_slice(&frame_row[*i..*i + 4]);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn integer_scaling_passes_through() {
+        let mut scaler = Scaler::new(640, 400, false);
+        assert!(!scaler.resize(1000, 1000, 10));
+        assert_eq!(scaler.output_dimensions(), (640, 400));
+        assert_eq!(scaler.to_frame_position(320, 200), (320, 200));
+        let frame = vec![7; 640 * 400 * 4];
+        let mut output = vec![0; 640 * 400 * 4];
+        scaler.draw(&frame, &mut output);
+        assert_eq!(frame, output);
+    }
+
+    #[test]
+    fn aspect_correction_fills_the_window() {
+        let mut scaler = Scaler::new(640, 400, true);
+        // 640x480 is 4:3
+        assert!(scaler.resize(660, 500, 10));
+        assert_eq!(scaler.output_dimensions(), (640, 480));
+        assert!(!scaler.resize(660, 500, 10));
+        // A wide window is pillarboxed
+        assert!(!scaler.resize(2000, 500, 10));
+        assert_eq!(scaler.to_frame_position(639, 479), (639, 399));
+        assert_eq!(scaler.to_frame_position(0, 240), (0, 200));
+        assert!(scaler.resize(2000, 260, 10));
+        assert_eq!(scaler.output_dimensions(), (320, 240));
+        assert_eq!(scaler.to_frame_position(160, 120), (320, 200));
+    }
+
+    #[test]
+    fn draw_resamples_rows() {
+        let mut scaler = Scaler::new(1, 2, true);
+        scaler.resize(10 * 5, 24 * 5, 0);
+        let (width, height) = scaler.output_dimensions();
+        let frame = [1, 1, 1, 1, 2, 2, 2, 2];
+        let mut output = vec![0; (width * height * 4) as usize];
+        scaler.draw(&frame, &mut output);
+        assert_eq!(output[0], 1);
+        assert_eq!(output[output.len() - 1], 2);
+    }
+}
@@ -1,30 +1,30 @@
+use std::time::{Duration, Instant};
+
+/// How long the cursor stays on or off, 8 frames of a VGA display at 70Hz
+pub const CURSOR_BLINK_RATE: Duration = Duration::from_micros(8_000_000 / 70);
+/// How long blinking text stays on or off, 16 frames of a VGA display at 70Hz
+pub const TEXT_BLINK_RATE: Duration = Duration::from_micros(16_000_000 / 70);
+
 pub struct Blink {
-    state: bool,
-    count: usize,
-    delay: usize,
+    start: Instant,
+    rate: Duration,
 }
 
 impl Blink {
-    pub fn new(delay: usize) -> Self {
+    pub fn new(rate: Duration) -> Self {
         Self {
-            state: true,
-            count: delay,
-            delay,
+            start: Instant::now(),
+            rate,
         }
     }
 
-    pub fn tic(&mut self) -> bool {
-        if self.count - 1 == 0 {
-            self.count = self.delay;
-            self.state = !self.state;
-        } else {
-            self.count -= 1;
-        }
-        self.state
+    /// Returns whether the blink is in its visible half, based on the time
+    /// elapsed since it was created or reset
+    pub fn is_on(&self) -> bool {
+        (self.start.elapsed().as_micros() / self.rate.as_micros().max(1)).is_multiple_of(2)
     }
 
     pub fn reset(&mut self) {
-        self.count = self.delay;
-        self.state = true;
+        self.start = Instant::now();
     }
 }
@@ -1,9 +1,32 @@
-use crate::terminal::Blink;
+use crate::terminal::{blink::CURSOR_BLINK_RATE, Blink};
+
+/// The shape of the cursor
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CursorStyle {
+    #[default]
+    Underline,
+    HalfBlock,
+    FullBlock,
+    Hidden,
+}
+
+impl CursorStyle {
+    /// Returns the height of the cursor in pixels for a font of `font_height`
+    pub fn height(&self, font_height: usize) -> usize {
+        match self {
+            CursorStyle::Underline => 2,
+            CursorStyle::HalfBlock => font_height / 2,
+            CursorStyle::FullBlock => font_height,
+            CursorStyle::Hidden => 0,
+        }
+    }
+}
 
 pub struct Cursor {
     pub column: usize,
     pub row: usize,
     pub height: usize,
+    pub blinking: bool,
     pub blink: Blink,
 }
 
@@ -13,19 +36,26 @@ impl Default for Cursor {
             column: 0,
             row: 0,
             height: 2,
-            blink: Blink::new(7),
+            blinking: true,
+            blink: Blink::new(CURSOR_BLINK_RATE),
         }
     }
 }
 
 impl Cursor {
-    pub fn new(height: usize) -> Self {
+    pub fn new(height: usize, blinking: bool) -> Self {
         Self {
             height,
+            blinking,
             ..Default::default()
         }
     }
 
+    /// Returns whether the cursor should currently be drawn
+    pub fn is_on(&self) -> bool {
+        self.height > 0 && (!self.blinking || self.blink.is_on())
+    }
+
     pub fn reset_blink(&mut self) {
         self.blink.reset();
     }
@@ -15,26 +15,22 @@ impl Buffer {
             width,
             height,
         };
-        buffer.clear();
+        buffer.clear(&BLACK_RGBA);
         buffer
     }
 
-    pub fn clear(&mut self) {
-        self.frame.fill_with_rgba(&BLACK_RGBA);
+    pub fn clear(&mut self, rgba: &Rgba) {
+        self.frame.fill_with_rgba(rgba);
     }
 
     pub fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, rgba: &Rgba) {
         self.frame.put_rgba(x, y, width, height, self.width, rgba);
     }
 
-    pub fn clear_rect(&mut self, x: usize, y: usize, width: usize, height: usize) {
-        self.fill_rect(x, y, width, height, &BLACK_RGBA);
-    }
-
-    pub fn scroll_up(&mut self, y: usize) {
+    pub fn scroll_up(&mut self, y: usize, rgba: &Rgba) {
         let start = y * self.width * 4;
         let buffer = self.frame[start..].to_vec();
         self.frame[0..buffer.len()].copy_from_slice(&buffer);
-        self.clear_rect(0, self.height - y, self.width, y);
+        self.fill_rect(0, self.height - y, self.width, y, rgba);
     }
 }
@@ -1,41 +1,169 @@
 mod buffer;
 mod get_and_put_pixels;
-use crate::terminal::{Blink, Cursor};
+use crate::terminal::{blink::TEXT_BLINK_RATE, Blink, Cursor, Palette};
 use ansiart::{
-    codepage437::{DrawFont, Font},
-    ega_palette::{EgaPalette, Rgba},
+    codepage437::{ascii, DrawFont, Font},
+    ega_palette::Rgba,
 };
 use buffer::Buffer;
 use get_and_put_pixels::GetAndPutRgba;
 
+/// The colour of a character, either an index into the palette or an RGBA value
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Colour {
     Indexed(usize),
     Rgba(Rgba),
 }
 
+/// A character on the display, kept so that the display can be redrawn when
+/// the palette changes, and so that it can be copied
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cell {
+    pub code: u8,
+    pub fg: Colour,
+    pub bg: Colour,
+    pub blink: bool,
+    /// The font slot the character is drawn with
+    pub font: usize,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Cell {
+            code: ascii::SPACE,
+            fg: Colour::Indexed(7),
+            bg: Colour::Indexed(0),
+            blink: false,
+            font: 0,
+        }
+    }
+}
+
+/// The number of font slots, as with SyncTERM these are the primary font,
+/// then the fonts for bright, blinking, and bright blinking characters
+pub const FONT_SLOTS: usize = 4;
+
 pub struct TerminalDisplay {
     font: Font,
+    fonts: [Font; FONT_SLOTS],
     pub width: usize,
     pub height: usize,
+    columns: usize,
+    rows: usize,
+    cells: Vec<Cell>,
     blink_on: Buffer,
     blink_off: Buffer,
     blink: Blink,
-    palette: EgaPalette,
+    palette: Palette,
 }
 
 impl TerminalDisplay {
-    pub fn new(columns: usize, rows: usize, palette: EgaPalette) -> Self {
-        let font = Font::default();
+    pub fn new(columns: usize, rows: usize, font: Font, palette: Palette) -> Self {
         let width = columns * font.width;
         let height = rows * font.height;
-        Self {
+        let mut display = Self {
+            fonts: std::array::from_fn(|_| font.clone()),
             font,
             width,
             height,
+            columns,
+            rows,
+            cells: vec![Cell::default(); columns * rows],
             blink_on: Buffer::new(width, height),
             blink_off: Buffer::new(width, height),
-            blink: Blink::new(12),
+            blink: Blink::new(TEXT_BLINK_RATE),
             palette,
+        };
+        display.clear();
+        display
+    }
+
+    pub fn font_width(&self) -> usize {
+        self.font.width
+    }
+
+    pub fn font_height(&self) -> usize {
+        self.font.height
+    }
+
+    pub fn cell(&self, column: usize, row: usize) -> Option<&Cell> {
+        if column < self.columns {
+            self.cells.get(row * self.columns + column)
+        } else {
+            None
+        }
+    }
+
+    /// Inverts the colours of a rectangle of characters in `frame`
+    pub fn invert_cells(
+        &self,
+        frame: &mut [u8],
+        column: usize,
+        row: usize,
+        columns: usize,
+        rows: usize,
+    ) {
+        frame.put_inverse(
+            column * self.font.width,
+            row * self.font.height,
+            columns * self.font.width,
+            rows * self.font.height,
+            self.width,
+        );
+    }
+
+    /// Draws `text` over a row of `frame` with the primary font, padded with
+    /// spaces to the width of the display
+    pub fn draw_text(&self, frame: &mut [u8], row: usize, text: &[u8], fg: &Rgba, bg: &Rgba) {
+        let glyph_line = self.font.width * 4;
+        for column in 0..self.columns {
+            let code = text.get(column).copied().unwrap_or(ascii::SPACE);
+            let font_rgba = self.font.to_bytes(code, fg, bg);
+            let x = column * self.font.width;
+            for (y, line) in font_rgba.chunks_exact(glyph_line).enumerate() {
+                let start = ((row * self.font.height + y) * self.width + x) * 4;
+                if let Some(bytes) = frame.get_mut(start..start + glyph_line) {
+                    bytes.copy_from_slice(line);
+                }
+            }
+        }
+    }
+
+    /// Loads `font` into one of the [FONT_SLOTS], stretched to the height of
+    /// the primary font, and redraws the characters which use the slot
+    pub fn set_font(&mut self, slot: usize, font: &Font) {
+        if slot >= FONT_SLOTS {
+            return;
+        }
+        self.fonts[slot] = font.with_height(self.font.height);
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                if self.cells[row * self.columns + column].font == slot {
+                    self.draw_cell(column, row);
+                }
+            }
+        }
+    }
+
+    /// Loads the primary font back into every slot
+    pub fn reset_fonts(&mut self) {
+        for slot in 0..FONT_SLOTS {
+            let font = self.font.clone();
+            self.set_font(slot, &font);
+        }
+    }
+
+    pub fn palette(&self) -> &Palette {
+        &self.palette
+    }
+
+    /// Changes the palette and redraws every character with it
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                self.draw_cell(column, row);
+            }
         }
     }
 
@@ -46,7 +174,7 @@ impl TerminalDisplay {
     }
 
     pub fn next_frame(&mut self, frame: &mut [u8]) {
-        if self.blink.tic() {
+        if self.blink.is_on() {
             frame.copy_from_slice(self.blink_on.frame.as_slice());
         } else {
             frame.copy_from_slice(self.blink_off.frame.as_slice());
@@ -54,48 +182,66 @@ impl TerminalDisplay {
     }
 
     pub fn scroll_up(&mut self) {
-        self.blink_on.scroll_up(self.font.height);
-        self.blink_off.scroll_up(self.font.height);
+        self.cells.drain(..self.columns);
+        self.cells.resize(self.columns * self.rows, Cell::default());
+        let rgba = self.palette[0];
+        self.blink_on.scroll_up(self.font.height, &rgba);
+        self.blink_off.scroll_up(self.font.height, &rgba);
     }
 
     pub fn clear(&mut self) {
-        self.blink_on.clear();
-        self.blink_off.clear();
+        self.cells.fill(Cell::default());
+        let rgba = self.palette[0];
+        self.blink_on.clear(&rgba);
+        self.blink_off.clear(&rgba);
     }
 
     pub fn clear_at(&mut self, column: usize, row: usize) {
-        let x = column * self.font.width;
-        let y = row * self.font.height;
-        self.blink_on
-            .clear_rect(x, y, self.font.width, self.font.height);
-        self.blink_off
-            .clear_rect(x, y, self.font.width, self.font.height);
+        if let Some(cell) = self.cells.get_mut(row * self.columns + column) {
+            *cell = Cell::default();
+            self.draw_cell(column, row);
+        }
     }
 
-    pub fn draw_glyph(
-        &mut self,
-        byte: u8,
-        column: usize,
-        row: usize,
-        fg: Colour,
-        bg: Colour,
-        blink: bool,
-    ) {
+    /// Moves the rest of the line from `column` right to insert a blank, or
+    /// left to delete the character at `column`
+    pub fn shift_line(&mut self, column: usize, row: usize, insert: bool) {
+        if column >= self.columns || row >= self.rows {
+            return;
+        }
+        let start = row * self.columns;
+        let line = &mut self.cells[start + column..start + self.columns];
+        if insert {
+            line.rotate_right(1);
+            line[0] = Cell::default();
+        } else {
+            line.rotate_left(1);
+            line[line.len() - 1] = Cell::default();
+        }
+        for column in column..self.columns {
+            self.draw_cell(column, row);
+        }
+    }
+
+    fn rgba(&self, colour: Colour) -> Rgba {
+        match colour {
+            Colour::Indexed(index) => self.palette[index],
+            Colour::Rgba(rgba) => rgba,
+        }
+    }
+
+    fn draw_cell(&mut self, column: usize, row: usize) {
+        let cell = self.cells[row * self.columns + column];
         let x = column * self.font.width;
         let y = row * self.font.height;
-        let fg = match fg {
-            Colour::Indexed(index) => self.palette[index].rgba,
-            Colour::Rgba(rgba) => rgba,
-        };
-        let bg = match bg {
-            Colour::Indexed(index) => self.palette[index].rgba,
-            Colour::Rgba(rgba) => rgba,
-        };
-        let font_rgba = self.font.to_bytes(byte, &fg, &bg);
+        let fg = self.rgba(cell.fg);
+        let bg = self.rgba(cell.bg);
+        let font = self.fonts.get(cell.font).unwrap_or(&self.font);
+        let font_rgba = font.to_bytes(cell.code, &fg, &bg);
         self.blink_on
             .frame
             .draw_font(x, y, self.font.width, self.width, &font_rgba);
-        if blink {
+        if cell.blink {
             self.blink_off
                 .fill_rect(x, y, self.font.width, self.font.height, &bg)
         } else {
@@ -104,4 +250,11 @@ impl TerminalDisplay {
                 .draw_font(x, y, self.font.width, self.width, &font_rgba);
         }
     }
+
+    pub fn draw_glyph(&mut self, column: usize, row: usize, cell: Cell) {
+        if let Some(existing) = self.cells.get_mut(row * self.columns + column) {
+            *existing = cell;
+            self.draw_cell(column, row);
+        }
+    }
 }
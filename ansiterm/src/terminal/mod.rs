@@ -1,14 +1,34 @@
 mod blink;
 mod cursor;
 mod display;
-use ansiart::ega_palette::{EgaPalette, Rgba};
+mod mouse;
+mod palette;
+mod profile;
+mod selection;
+use ansiart::{
+    atascii,
+    codepage437::Font,
+    ega_palette::Rgba,
+    petscii::{self, Control},
+};
 use blink::Blink;
 use cursor::Cursor;
-use display::{Colour, TerminalDisplay};
+pub use cursor::CursorStyle;
+use display::TerminalDisplay;
+pub use display::{Cell, Colour, FONT_SLOTS};
+use mouse::MouseMode;
+pub use mouse::{MouseButton, MouseEvent, MouseModifiers};
+pub use palette::{Palette, PaletteError};
+pub use profile::Profile;
+pub use selection::Selection;
 
 pub struct Terminal {
     display: TerminalDisplay,
+    palette: Palette,
     cursor: Cursor,
+    cursor_style: CursorStyle,
+    cursor_blinking: bool,
+    cursor_visible: bool,
     stored_cursor: Option<Cursor>,
     columns: usize,
     rows: usize,
@@ -17,16 +37,48 @@ pub struct Terminal {
     bg: usize,
     blink: bool,
     bold: bool,
+    bright_font: bool,
+    blink_font: bool,
     pablo_true_colour_bg: Option<Rgba>,
     pablo_true_colour_fg: Option<Rgba>,
     ice_colors: bool,
+    profile: Profile,
+    reverse: bool,
+    escaped: bool,
+    responses: Vec<u8>,
+    mouse_mode: MouseMode,
 }
 
 impl Terminal {
     pub fn new(columns: usize, rows: usize, ice_colors: bool) -> Self {
+        Terminal::with_appearance(
+            columns,
+            rows,
+            ice_colors,
+            Font::default(),
+            Palette::default(),
+            CursorStyle::default(),
+        )
+    }
+
+    /// Constructs a terminal which draws with `font` and `palette`.
+    pub fn with_appearance(
+        columns: usize,
+        rows: usize,
+        ice_colors: bool,
+        font: Font,
+        palette: Palette,
+        cursor_style: CursorStyle,
+    ) -> Self {
+        let display = TerminalDisplay::new(columns, rows, font, palette.clone());
+        let cursor = Cursor::new(cursor_style.height(display.font_height()), true);
         Self {
-            display: TerminalDisplay::new(columns, rows, EgaPalette::ansi()),
-            cursor: Cursor::new(2),
+            display,
+            palette,
+            cursor,
+            cursor_style,
+            cursor_blinking: true,
+            cursor_visible: true,
             stored_cursor: None,
             columns,
             rows,
@@ -35,9 +87,113 @@ impl Terminal {
             bg: 0,
             blink: false,
             bold: false,
+            bright_font: false,
+            blink_font: false,
             pablo_true_colour_bg: None,
             pablo_true_colour_fg: None,
             ice_colors,
+            profile: Profile::default(),
+            reverse: false,
+            escaped: false,
+            responses: vec![],
+            mouse_mode: MouseMode::default(),
+        }
+    }
+
+    /// Clears the display and returns the cursor and attributes to their
+    /// initial state, ready for the input to be played again.
+    pub fn reset(&mut self) {
+        self.display.clear();
+        self.cursor = Cursor::new(
+            self.cursor_style.height(self.display.font_height()),
+            self.cursor_blinking,
+        );
+        self.cursor_visible = true;
+        self.mouse_mode = MouseMode::default();
+        self.stored_cursor = None;
+        self.wrap = false;
+        self.reverse = false;
+        self.escaped = false;
+        self.bright_font = false;
+        self.blink_font = false;
+        self.display.reset_fonts();
+        self.select_graphics_rendition(&[0]);
+        self.display.set_palette(self.palette.clone());
+    }
+
+    /// Follows the conventions of another computer, the font and palette of
+    /// the profile are given separately to [Terminal::with_appearance]
+    pub fn set_profile(&mut self, profile: Profile) {
+        self.profile = profile;
+        self.fg = profile.foreground();
+    }
+
+    /// Changes the shape of the cursor, which is also restored by [Terminal::reset].
+    pub fn set_cursor_style(&mut self, cursor_style: CursorStyle) {
+        self.cursor_style = cursor_style;
+        self.cursor.height = cursor_style.height(self.display.font_height());
+    }
+
+    /// Sets whether the cursor blinks, which is also restored by [Terminal::reset].
+    pub fn set_cursor_blink(&mut self, blinking: bool) {
+        self.cursor_blinking = blinking;
+        self.cursor.blinking = blinking;
+    }
+
+    /// Changes the cursor as with DECSCUSR (`ESC[n q`), 0 and 1 select a
+    /// blinking block, 2 a steady block, 3 a blinking underline, and 4 a
+    /// steady underline.
+    pub fn select_cursor_style(&mut self, value: usize) {
+        let (cursor_style, blinking) = match value {
+            0 | 1 => (CursorStyle::FullBlock, true),
+            2 => (CursorStyle::FullBlock, false),
+            3 => (CursorStyle::Underline, true),
+            4 => (CursorStyle::Underline, false),
+            _ => return,
+        };
+        // Only the cursor changes, so that [Terminal::reset] returns to the
+        // style given by [Terminal::set_cursor_style]
+        self.cursor.height = cursor_style.height(self.display.font_height());
+        self.cursor.blinking = blinking;
+        self.cursor.reset_blink();
+    }
+
+    /// Loads one of SyncTERM's numbered fonts into a slot, as with
+    /// `ESC[slot;font D`, fonts which aren't bundled are ignored.
+    pub fn select_font(&mut self, slot: usize, number: usize) {
+        if let Ok(font) = Font::syncterm(number) {
+            self.display.set_font(slot, &font);
+        }
+    }
+
+    /// Changes the palette, redrawing everything already on the display, this
+    /// palette is also restored by [Terminal::reset] and [Terminal::reset_palette].
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette.clone();
+        self.display.set_palette(palette);
+    }
+
+    /// Changes individual colours of the palette, as with `ESC]4;n;rgb:rr/gg/bb`.
+    pub fn set_palette_colours(&mut self, colours: &[(usize, [u8; 3])]) {
+        let mut palette = self.display.palette().clone();
+        for (index, rgb) in colours {
+            palette.set(*index, *rgb);
+        }
+        self.display.set_palette(palette);
+    }
+
+    /// Restores colours of the palette to those given by [Terminal::set_palette],
+    /// or all of them if `indexes` is empty, as with `ESC]104`.
+    pub fn reset_palette(&mut self, indexes: &[usize]) {
+        if indexes.is_empty() {
+            self.display.set_palette(self.palette.clone());
+        } else {
+            let mut palette = self.display.palette().clone();
+            for index in indexes.iter().filter(|index| **index < 16) {
+                let [r, g, b, _] = self.palette[*index];
+                palette.set(*index, [r, g, b]);
+            }
+            self.display.set_palette(palette);
         }
     }
 
@@ -45,11 +201,50 @@ impl Terminal {
         (self.display.width as u32, self.display.height as u32)
     }
 
+    /// Returns the character at `column` and `row`
+    pub fn cell(&self, column: usize, row: usize) -> Option<&Cell> {
+        self.display.cell(column, row)
+    }
+
+    /// Returns the column and row of the character under a pixel of the
+    /// frame, clamped to the terminal
+    pub fn cell_at(&self, x: usize, y: usize) -> (usize, usize) {
+        (
+            (x / self.display.font_width()).min(self.columns - 1),
+            (y / self.display.font_height()).min(self.rows - 1),
+        )
+    }
+
+    /// Highlights a selection on a frame produced by [Terminal::next_frame]
+    pub fn draw_selection(&self, frame: &mut [u8], selection: &Selection) {
+        let columns = selection.columns();
+        let rows = selection.rows();
+        self.display.invert_cells(
+            frame,
+            *columns.start(),
+            *rows.start(),
+            columns.end() - columns.start() + 1,
+            rows.end() - rows.start() + 1,
+        );
+    }
+
+    /// Draws a line of code page 437 `text` over the bottom row of a frame
+    /// produced by [Terminal::next_frame], in bright white on blue
+    pub fn draw_status(&self, frame: &mut [u8], text: &[u8]) {
+        self.display.draw_text(
+            frame,
+            self.rows - 1,
+            text,
+            &self.palette[15],
+            &self.palette[1],
+        );
+    }
+
     pub fn select_graphics_rendition(&mut self, values: &[usize]) {
         for value in values {
             match value {
                 0 => {
-                    self.fg = 7;
+                    self.fg = self.profile.foreground();
                     self.bg = 0;
                     self.bold = false;
                     self.blink = false;
@@ -75,14 +270,28 @@ impl Terminal {
     }
 
     pub fn set_screen_mode(&mut self, value: usize) {
-        if value == 7 {
-            self.wrap = true;
+        if self.mouse_mode.set(value, true) {
+            return;
+        }
+        match value {
+            7 => self.wrap = true,
+            25 => self.cursor_visible = true,
+            31 => self.bright_font = true,
+            34 => self.blink_font = true,
+            _ => {}
         }
     }
 
     pub fn reset_screen_mode(&mut self, value: usize) {
-        if value == 7 {
-            self.wrap = false;
+        if self.mouse_mode.set(value, false) {
+            return;
+        }
+        match value {
+            7 => self.wrap = false,
+            25 => self.cursor_visible = false,
+            31 => self.bright_font = false,
+            34 => self.blink_font = false,
+            _ => {}
         }
     }
 
@@ -119,17 +328,105 @@ impl Terminal {
     }
 
     pub fn tab(&mut self) {
-        self.cursor_forward(8);
+        // PETSCII uses the same code to allow switching between cases
+        if self.profile != Profile::Petscii {
+            self.cursor_forward(8);
+        }
     }
 
     pub fn next_frame(&mut self, frame: &mut [u8]) {
         self.display.next_frame(frame);
-        if self.cursor.blink.tic() && self.cursor.row < self.rows {
+        if self.cursor_visible && self.cursor.is_on() && self.cursor.row < self.rows {
             self.display.draw_cursor(frame, &self.cursor)
         }
     }
 
     pub fn literal(&mut self, byte: u8) {
+        match self.profile {
+            Profile::Petscii => self.petscii(byte),
+            Profile::Atascii => self.atascii(byte),
+            _ => self.draw(byte),
+        }
+    }
+
+    // Acts on a PETSCII control code, or draws the character's screen code
+    fn petscii(&mut self, byte: u8) {
+        match petscii::control(byte) {
+            Some(Control::Colour(colour)) => self.fg = colour,
+            Some(Control::Return) => {
+                self.reverse = false;
+                self.cursor.column = 0;
+                self.next_line();
+            }
+            Some(Control::ReverseOn) => self.reverse = true,
+            Some(Control::ReverseOff) => self.reverse = false,
+            Some(Control::Clear) => self.clear_display(),
+            Some(Control::Home) => self.move_cursor_to(0, 0),
+            Some(Control::CursorUp) => self.cursor_up(1),
+            Some(Control::CursorDown) => self.cursor_down(1),
+            Some(Control::CursorLeft) => self.petscii_cursor_left(),
+            Some(Control::CursorRight) => self.cursor_forward(1),
+            Some(Control::Delete) => {
+                self.petscii_cursor_left();
+                self.display
+                    .shift_line(self.cursor.column, self.cursor.row, false);
+            }
+            Some(Control::Insert) => {
+                self.display
+                    .shift_line(self.cursor.column, self.cursor.row, true)
+            }
+            Some(Control::Charset(charset)) => self.display.set_font(0, &charset.font()),
+            None => {
+                if let Some(code) = petscii::screen_code(byte) {
+                    // The reversed characters are in the second half of the font
+                    self.draw(if self.reverse { code | 0x80 } else { code });
+                }
+            }
+        }
+    }
+
+    // As with [petscii::PetsciiDecoder], moving left from the first column
+    // continues from the end of the line above
+    fn petscii_cursor_left(&mut self) {
+        if self.cursor.column > 0 {
+            self.cursor.column -= 1;
+        } else if self.cursor.row > 0 {
+            self.cursor.column = self.columns - 1;
+            self.cursor.row = self.cursor.row.min(self.rows) - 1;
+        }
+    }
+
+    // Acts on an ATASCII control code, or draws the character
+    fn atascii(&mut self, byte: u8) {
+        if self.escaped {
+            self.escaped = false;
+            self.draw(byte);
+            return;
+        }
+        match atascii::control(byte) {
+            Some(atascii::Control::Escape) => self.escaped = true,
+            Some(atascii::Control::CursorUp) => self.cursor_up(1),
+            Some(atascii::Control::CursorDown) => self.cursor_down(1),
+            Some(atascii::Control::CursorLeft) => self.cursor_back(1),
+            Some(atascii::Control::CursorRight) => self.cursor_forward(1),
+            Some(atascii::Control::Clear) => self.clear_display(),
+            Some(atascii::Control::Backspace) if self.cursor.column > 0 => {
+                self.cursor_back(1);
+                self.display.clear_at(self.cursor.column, self.cursor.row);
+            }
+            Some(atascii::Control::Tab) => self.cursor_forward(8 - self.cursor.column % 8),
+            Some(atascii::Control::EndOfLine) => {
+                self.cursor.column = 0;
+                self.next_line();
+            }
+            // Moving lines and characters, and changing the tab stops, are
+            // only supported by [atascii::AtasciiDecoder]
+            Some(_) => {}
+            None => self.draw(byte),
+        }
+    }
+
+    fn draw(&mut self, byte: u8) {
         if self.cursor.row == self.rows {
             self.display.scroll_up();
             self.cursor.row -= 1;
@@ -143,14 +440,19 @@ impl Terminal {
             Some(rgba) => Colour::Rgba(rgba),
             None => Colour::Indexed(self.bg),
         };
-        self.display.draw_glyph(
-            byte,
-            self.cursor.column,
-            self.cursor.row,
+        // With `ESC[?31h` and `ESC[?34h` bright and blinking characters are
+        // drawn with the fonts in the later slots
+        let bright_slot = if self.bold && self.bright_font { 1 } else { 0 };
+        let blink_slot = if self.blink && self.blink_font { 2 } else { 0 };
+        let cell = Cell {
+            code: byte,
             fg,
             bg,
-            self.blink && !self.ice_colors,
-        );
+            blink: self.blink && !self.ice_colors && self.profile.blinks(),
+            font: bright_slot + blink_slot,
+        };
+        self.display
+            .draw_glyph(self.cursor.column, self.cursor.row, cell);
         if self.cursor.column == self.columns - 1 {
             self.cursor.column = 0;
             self.cursor.row += 1;
@@ -160,6 +462,13 @@ impl Terminal {
     }
 
     pub fn line_feed(&mut self) {
+        // A PETSCII return moves to the next line by itself
+        if self.profile != Profile::Petscii {
+            self.next_line();
+        }
+    }
+
+    fn next_line(&mut self) {
         if self.cursor.row == self.rows {
             if self.wrap {
                 self.cursor.row = 0;
@@ -172,7 +481,10 @@ impl Terminal {
     }
 
     pub fn carriage_return(&mut self) {
-        self.cursor.column = 0;
+        match self.profile {
+            Profile::Petscii => self.petscii(petscii::RETURN),
+            _ => self.cursor.column = 0,
+        }
     }
 
     pub fn save_cursor_position(&mut self) {
@@ -258,4 +570,77 @@ impl Terminal {
     pub fn rgb_fg(&mut self, r: u8, g: u8, b: u8) {
         self.pablo_true_colour_fg = Some([r, g, b, 255]);
     }
+
+    /// Answers a device status report, `ESC[5n` reports the terminal as ready
+    /// and `ESC[6n` reports the cursor position.
+    pub fn device_status_report(&mut self, value: usize) {
+        match value {
+            5 => self.respond(b"\x1b[0n"),
+            6 => {
+                let row = self.cursor.row.min(self.rows - 1) + 1;
+                let column = self.cursor.column + 1;
+                self.respond(format!("\x1b[{row};{column}R").as_bytes());
+            }
+            _ => {}
+        }
+    }
+
+    /// Answers a device attributes request as a VT100 with no options.
+    pub fn device_attributes(&mut self) {
+        self.respond(b"\x1b[?1;0c");
+    }
+
+    /// Returns `true` if the input source has asked for mouse events, with
+    /// `ESC[?1000h`, `ESC[?1002h`, or `ESC[?1003h`.
+    pub fn is_tracking_mouse(&self) -> bool {
+        self.mouse_mode.is_tracking()
+    }
+
+    /// Reports a mouse event at `column` and `row` to the input source, if
+    /// it has asked for it, using the SGR encoding when `ESC[?1006h` is set.
+    pub fn report_mouse(
+        &mut self,
+        event: MouseEvent,
+        column: usize,
+        row: usize,
+        modifiers: MouseModifiers,
+    ) {
+        if let Some(bytes) = self.mouse_mode.encode(event, column, row, modifiers) {
+            self.respond(&bytes);
+        }
+    }
+
+    fn respond(&mut self, bytes: &[u8]) {
+        self.responses.extend_from_slice(bytes);
+    }
+
+    /// Returns any bytes the terminal has generated in response to queries
+    /// since the last call, to be sent back to the input source.
+    pub fn take_responses(&mut self) -> Option<Vec<u8>> {
+        if self.responses.is_empty() {
+            None
+        } else {
+            Some(std::mem::take(&mut self.responses))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reset_restores_configured_cursor_style() {
+        let mut term = Terminal::new(80, 25, false);
+        let font_height = term.display.font_height();
+        term.set_cursor_style(CursorStyle::HalfBlock);
+        term.select_cursor_style(2);
+        assert_eq!(term.cursor.height, font_height);
+        assert!(!term.cursor.blinking);
+        term.select_font(1, 36);
+        term.reset();
+        assert_eq!(term.cursor_style, CursorStyle::HalfBlock);
+        assert_eq!(term.cursor.height, font_height / 2);
+        assert!(term.cursor.blinking);
+    }
 }
@@ -0,0 +1,154 @@
+/// A mouse button which can be reported to the input source
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+}
+
+/// Something the mouse did, which may be reported to the input source
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MouseEvent {
+    Press(MouseButton),
+    Release(MouseButton),
+    /// The mouse moved to another character, with the button held, if any
+    Motion(Option<MouseButton>),
+    WheelUp,
+    WheelDown,
+}
+
+/// The modifier keys held during a [MouseEvent]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MouseModifiers {
+    pub shift: bool,
+    pub alt: bool,
+    pub ctrl: bool,
+}
+
+// Which mouse events are reported, from the xterm private modes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+enum Tracking {
+    #[default]
+    Off,
+    // ?1000, presses, releases and the wheel
+    Normal,
+    // ?1002, also motion whilst a button is held
+    ButtonEvent,
+    // ?1003, also any motion
+    AnyEvent,
+}
+
+/// The mouse reporting modes enabled by the input source
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MouseMode {
+    tracking: Tracking,
+    // ?1006, reports as "ESC[<b;x;yM" rather than bytes offset by 32
+    sgr: bool,
+}
+
+impl MouseMode {
+    /// Enables or disables one of the xterm private modes, returns `false`
+    /// if `value` isn't a mouse mode.
+    pub fn set(&mut self, value: usize, enabled: bool) -> bool {
+        let tracking = match value {
+            1000 => Tracking::Normal,
+            1002 => Tracking::ButtonEvent,
+            1003 => Tracking::AnyEvent,
+            1006 => {
+                self.sgr = enabled;
+                return true;
+            }
+            _ => return false,
+        };
+        if enabled {
+            self.tracking = tracking;
+        } else if self.tracking == tracking {
+            self.tracking = Tracking::Off;
+        }
+        true
+    }
+
+    pub fn is_tracking(&self) -> bool {
+        self.tracking != Tracking::Off
+    }
+
+    /// Encodes an event at the zero-based `column` and `row`, or returns
+    /// `None` if the event isn't reported in the current mode
+    pub fn encode(
+        &self,
+        event: MouseEvent,
+        column: usize,
+        row: usize,
+        modifiers: MouseModifiers,
+    ) -> Option<Vec<u8>> {
+        let button_code = |button: MouseButton| match button {
+            MouseButton::Left => 0,
+            MouseButton::Middle => 1,
+            MouseButton::Right => 2,
+        };
+        let (code, release) = match event {
+            _ if self.tracking == Tracking::Off => return None,
+            MouseEvent::Press(button) => (button_code(button), false),
+            MouseEvent::Release(button) if self.sgr => (button_code(button), true),
+            MouseEvent::Release(_) => (3, true),
+            MouseEvent::Motion(Some(button)) if self.tracking >= Tracking::ButtonEvent => {
+                (button_code(button) + 32, false)
+            }
+            MouseEvent::Motion(None) if self.tracking == Tracking::AnyEvent => (3 + 32, false),
+            MouseEvent::Motion(_) => return None,
+            MouseEvent::WheelUp => (64, false),
+            MouseEvent::WheelDown => (65, false),
+        };
+        let code = code
+            + if modifiers.shift { 4 } else { 0 }
+            + if modifiers.alt { 8 } else { 0 }
+            + if modifiers.ctrl { 16 } else { 0 };
+        let (x, y) = (column + 1, row + 1);
+        if self.sgr {
+            let terminator = if release { 'm' } else { 'M' };
+            Some(format!("\x1b[<{code};{x};{y}{terminator}").into_bytes())
+        } else {
+            // Coordinates beyond 223 can't be represented in a single byte
+            let x = u8::try_from(x + 32).ok()?;
+            let y = u8::try_from(y + 32).ok()?;
+            Some(vec![0x1b, b'[', b'M', code + 32, x, y])
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn encode_mouse_events() {
+        let mut mode = MouseMode::default();
+        let modifiers = MouseModifiers::default();
+        let press = MouseEvent::Press(MouseButton::Left);
+        assert_eq!(mode.encode(press, 0, 0, modifiers), None);
+        mode.set(1000, true);
+        assert_eq!(
+            mode.encode(press, 0, 0, modifiers),
+            Some(b"\x1b[M !!".to_vec())
+        );
+        assert_eq!(
+            mode.encode(MouseEvent::Motion(Some(MouseButton::Left)), 1, 0, modifiers),
+            None
+        );
+        mode.set(1006, true);
+        let ctrl = MouseModifiers {
+            ctrl: true,
+            ..modifiers
+        };
+        assert_eq!(
+            mode.encode(MouseEvent::Release(MouseButton::Right), 79, 24, ctrl),
+            Some(b"\x1b[<18;80;25m".to_vec())
+        );
+        assert_eq!(
+            mode.encode(MouseEvent::WheelDown, 2, 3, modifiers),
+            Some(b"\x1b[<65;3;4M".to_vec())
+        );
+        mode.set(1000, false);
+        assert!(!mode.is_tracking());
+    }
+}
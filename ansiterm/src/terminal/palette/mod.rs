@@ -0,0 +1,220 @@
+mod palette_error;
+use ansiart::{
+    atascii,
+    ega_palette::{EgaPalette, Rgb, Rgba, BLACK_RGBA, CGA_ORDER},
+    petscii,
+    sauce::Sauce,
+};
+pub use palette_error::PaletteError;
+use std::{fs, path::Path};
+
+const XBIN_HEAD: [u8; 5] = [b'X', b'B', b'I', b'N', 0x1a];
+const XBIN_HEADER_LEN: usize = 11;
+const ADF_HEADER_LEN: usize = 1;
+const IDF_FONT_LEN: usize = 4096;
+const VGA_PALETTE_LEN: usize = 48;
+
+// Swaps the red and blue bits of a colour index, to convert between the order
+// colours are stored in by the hardware and the order used by ANSI sequences
+fn swap_order(index: usize) -> usize {
+    (index & 0b1010) | ((index & 0b0001) << 2) | ((index & 0b0100) >> 2)
+}
+
+// Converts a 6 bit VGA DAC value to an 8 bit value
+fn six_to_eight_bits(value: u8) -> u8 {
+    (value << 2) | (value >> 4)
+}
+
+// Returns the bytes of a file with any SAUCE record removed
+fn without_sauce(bytes: &[u8]) -> &[u8] {
+    match Sauce::try_from(bytes) {
+        Ok(sauce) if sauce.size() <= bytes.len() => &bytes[..bytes.len() - sauce.size()],
+        _ => bytes,
+    }
+}
+
+/// The 16 colours available to the terminal, stored in the order used by ANSI
+/// escape sequences, so that index 1 is red and index 4 is blue.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Palette {
+    colours: [Rgba; 16],
+}
+
+impl Default for Palette {
+    /// The standard EGA colours
+    fn default() -> Self {
+        Palette::from(&EgaPalette::ansi())
+    }
+}
+
+impl From<&EgaPalette> for Palette {
+    /// Takes the first 16 colours of an [EgaPalette] which is already in ANSI
+    /// order, such as `EgaPalette::ansi()`, missing colours are black
+    fn from(ega_palette: &EgaPalette) -> Self {
+        let mut colours = [BLACK_RGBA; 16];
+        for (index, colour) in colours.iter_mut().enumerate() {
+            if let Some(ega_colour) = ega_palette.get(index) {
+                *colour = ega_colour.rgba;
+            }
+        }
+        Palette { colours }
+    }
+}
+
+impl From<[Rgb; 16]> for Palette {
+    /// Constructs a palette from RGB values in ANSI order
+    fn from(rgb: [Rgb; 16]) -> Self {
+        Palette {
+            colours: rgb.map(|[r, g, b]| [r, g, b, 255]),
+        }
+    }
+}
+
+impl std::ops::Index<usize> for Palette {
+    type Output = Rgba;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.colours[index]
+    }
+}
+
+impl Palette {
+    /// The ANSI colours as drawn by Amiga terminals with the Workbench's 12
+    /// bit colours, which have a true yellow rather than the IBM PC's brown
+    pub fn amiga() -> Palette {
+        Palette::from([
+            [0x00, 0x00, 0x00],
+            [0xaa, 0x00, 0x00],
+            [0x00, 0xaa, 0x00],
+            [0xaa, 0xaa, 0x00],
+            [0x00, 0x00, 0xaa],
+            [0xaa, 0x00, 0xaa],
+            [0x00, 0xaa, 0xaa],
+            [0xaa, 0xaa, 0xaa],
+            [0x55, 0x55, 0x55],
+            [0xff, 0x55, 0x55],
+            [0x55, 0xff, 0x55],
+            [0xff, 0xff, 0x55],
+            [0x55, 0x55, 0xff],
+            [0xff, 0x55, 0xff],
+            [0x55, 0xff, 0xff],
+            [0xff, 0xff, 0xff],
+        ])
+    }
+
+    /// The Commodore 64's colours as measured by Pepto, unlike the other
+    /// palettes these are in the C64's own order, so that index 1 is white
+    /// and index 6 is blue
+    pub fn c64() -> Palette {
+        Palette::from(petscii::PALETTE)
+    }
+
+    /// The Atari's text playfield, which only has two colours, the background
+    /// at index 0 and the characters at index 1
+    pub fn atari() -> Palette {
+        let mut colours = [BLACK_RGBA; 16];
+        for (index, colour) in [atascii::BACKGROUND, atascii::FOREGROUND]
+            .iter()
+            .enumerate()
+        {
+            colours[index] = [colour[0], colour[1], colour[2], 255];
+        }
+        Palette { colours }
+    }
+
+    /// Constructs a palette from 48 bytes of 6 bit VGA DAC values, stored in
+    /// the order of the hardware's colour attributes
+    pub fn from_vga(bytes: &[u8]) -> Result<Palette, PaletteError> {
+        let bytes = bytes
+            .get(..VGA_PALETTE_LEN)
+            .ok_or(PaletteError::InvalidPalette)?;
+        if bytes.iter().any(|value| *value > 63) {
+            return Err(PaletteError::InvalidPalette);
+        }
+        let mut colours = [BLACK_RGBA; 16];
+        for (index, rgb) in bytes.chunks_exact(3).enumerate() {
+            colours[swap_order(index)] = [
+                six_to_eight_bits(rgb[0]),
+                six_to_eight_bits(rgb[1]),
+                six_to_eight_bits(rgb[2]),
+                255,
+            ];
+        }
+        Ok(Palette { colours })
+    }
+
+    /// Reads the palette from the header of an XBin file, returns `None` if
+    /// the file doesn't have one
+    pub fn from_xbin(bytes: &[u8]) -> Result<Option<Palette>, PaletteError> {
+        if bytes.len() < XBIN_HEADER_LEN || bytes[..XBIN_HEAD.len()] != XBIN_HEAD {
+            return Err(PaletteError::InvalidPalette);
+        }
+        if bytes[10] & 1 == 0 {
+            return Ok(None);
+        }
+        Palette::from_vga(&bytes[XBIN_HEADER_LEN..]).map(Some)
+    }
+
+    /// Reads the palette from an Artworx (ADF) file, which stores all 64 EGA
+    /// colours after the version byte
+    pub fn from_adf(bytes: &[u8]) -> Result<Palette, PaletteError> {
+        let all = bytes
+            .get(ADF_HEADER_LEN..ADF_HEADER_LEN + 64 * 3)
+            .ok_or(PaletteError::InvalidPalette)?;
+        let mut vga = Vec::with_capacity(VGA_PALETTE_LEN);
+        for ega_index in CGA_ORDER.map(usize::from) {
+            vga.extend_from_slice(&all[ega_index * 3..ega_index * 3 + 3]);
+        }
+        Palette::from_vga(&vga)
+    }
+
+    /// Reads the palette from the end of an iCE Draw (IDF) file
+    pub fn from_idf(bytes: &[u8]) -> Result<Palette, PaletteError> {
+        let bytes = without_sauce(bytes);
+        if bytes.len() < VGA_PALETTE_LEN + IDF_FONT_LEN {
+            return Err(PaletteError::InvalidPalette);
+        }
+        Palette::from_vga(&bytes[bytes.len() - VGA_PALETTE_LEN..])
+    }
+
+    /// Reads the palette embedded in an XBin, ADF, or IDF file, recognised by
+    /// its extension, returns `None` for any other file
+    pub fn read_embedded(path: impl AsRef<Path>) -> Result<Option<Palette>, PaletteError> {
+        let extension = match path.as_ref().extension() {
+            Some(extension) => extension.to_string_lossy().to_lowercase(),
+            None => return Ok(None),
+        };
+        if !matches!(extension.as_str(), "xb" | "adf" | "idf") {
+            return Ok(None);
+        }
+        let bytes = fs::read(&path).map_err(|_| {
+            PaletteError::CannotReadFile(path.as_ref().to_string_lossy().to_string())
+        })?;
+        match extension.as_str() {
+            "xb" => Palette::from_xbin(&bytes),
+            "adf" => Palette::from_adf(&bytes).map(Some),
+            _ => Palette::from_idf(&bytes).map(Some),
+        }
+    }
+
+    /// Reads a palette file, either the palette embedded in an XBin, ADF, or
+    /// IDF file, or a raw file of 48 bytes of 6 bit VGA DAC values
+    pub fn read(path: impl AsRef<Path>) -> Result<Palette, PaletteError> {
+        if let Some(palette) = Palette::read_embedded(&path)? {
+            return Ok(palette);
+        }
+        match fs::read(&path) {
+            Ok(bytes) => Palette::from_vga(&bytes).map_err(|_| PaletteError::NoPalette),
+            Err(_) => Err(PaletteError::CannotReadFile(
+                path.as_ref().to_string_lossy().to_string(),
+            )),
+        }
+    }
+
+    /// Changes the colour at `index`, out of range indexes are ignored
+    pub fn set(&mut self, index: usize, rgb: Rgb) {
+        if let Some(colour) = self.colours.get_mut(index) {
+            *colour = [rgb[0], rgb[1], rgb[2], 255];
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use std::{error, fmt};
+
+/// Error produced when reading a palette
+#[derive(Debug)]
+pub enum PaletteError {
+    CannotReadFile(String),
+    InvalidPalette,
+    NoPalette,
+}
+
+impl fmt::Display for PaletteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaletteError::CannotReadFile(path) => write!(f, "Cannot read {path}"),
+            PaletteError::InvalidPalette => write!(f, "Invalid palette"),
+            PaletteError::NoPalette => write!(f, "File does not contain a palette"),
+        }
+    }
+}
+
+impl error::Error for PaletteError {}
@@ -0,0 +1,155 @@
+use crate::terminal::Palette;
+use ansiart::{
+    atascii,
+    codepage437::{raw, Font},
+    petscii::Charset,
+};
+
+/// The computer whose conventions the terminal follows, which decides its
+/// font, palette, and how characters are interpreted
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Profile {
+    /// An IBM PC, drawing codepage 437 with the EGA palette
+    #[default]
+    Dos,
+    /// An Amiga, drawing with Topaz and the Workbench colours, without blinking
+    Amiga,
+    /// A Commodore 64, interpreting PETSCII control codes for colour, reverse
+    /// video, and cursor movement on a 40 column screen
+    Petscii,
+    /// An Atari 8-bit computer, interpreting ATASCII control codes on its two
+    /// colour, 40 by 24 text playfield
+    Atascii,
+}
+
+impl Profile {
+    /// Returns the profile for a file with the conventional extension of
+    /// `.asc` for Amiga ANSI or `.seq` for PETSCII
+    pub fn from_extension(extension: &str) -> Option<Profile> {
+        match extension.to_lowercase().as_str() {
+            "asc" => Some(Profile::Amiga),
+            "seq" => Some(Profile::Petscii),
+            _ => None,
+        }
+    }
+
+    pub fn font(&self) -> Font {
+        match self {
+            Profile::Dos => Font::default(),
+            Profile::Amiga => Font::try_from(raw::TOPAZ_A500_F16.as_ref()).expect("font"),
+            Profile::Petscii => Charset::Unshifted.font(),
+            Profile::Atascii => atascii::font(),
+        }
+    }
+
+    pub fn palette(&self) -> Palette {
+        match self {
+            Profile::Dos => Palette::default(),
+            Profile::Amiga => Palette::amiga(),
+            Profile::Petscii => Palette::c64(),
+            Profile::Atascii => Palette::atari(),
+        }
+    }
+
+    /// Returns the number of columns on the screen
+    pub fn columns(&self) -> usize {
+        match self {
+            Profile::Dos | Profile::Amiga => 80,
+            Profile::Petscii | Profile::Atascii => 40,
+        }
+    }
+
+    /// Returns the number of rows on the screen
+    pub fn rows(&self) -> usize {
+        match self {
+            Profile::Atascii => 24,
+            _ => 25,
+        }
+    }
+
+    /// Returns the foreground colour the screen starts with, which is light
+    /// blue for the C64
+    pub fn foreground(&self) -> usize {
+        match self {
+            Profile::Dos | Profile::Amiga => 7,
+            Profile::Petscii => 14,
+            Profile::Atascii => 1,
+        }
+    }
+
+    /// Returns whether the input contains escape sequences, rather than being
+    /// a stream of another character set's codes
+    pub fn is_ansi(&self) -> bool {
+        matches!(self, Profile::Dos | Profile::Amiga)
+    }
+
+    /// Returns whether the blink attribute makes characters blink
+    pub fn blinks(&self) -> bool {
+        !matches!(self, Profile::Amiga)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::terminal::{Colour, CursorStyle, Profile, Terminal};
+    use ansiart::petscii::{self, PetsciiDecoder};
+
+    #[test]
+    fn petscii_control_codes() {
+        let profile = Profile::Petscii;
+        let mut term = Terminal::with_appearance(
+            profile.columns(),
+            25,
+            false,
+            profile.font(),
+            profile.palette(),
+            CursorStyle::default(),
+        );
+        term.set_profile(profile);
+        for byte in b"A\x1cB\x12C\x0dD" {
+            term.literal(*byte);
+        }
+        let cell = |column, row| term.cell(column, row).copied().unwrap();
+        assert_eq!(
+            (cell(0, 0).code, cell(0, 0).fg),
+            (0x01, Colour::Indexed(14))
+        );
+        assert_eq!((cell(1, 0).code, cell(1, 0).fg), (0x02, Colour::Indexed(2)));
+        assert_eq!(cell(2, 0).code, 0x83);
+        assert_eq!(cell(0, 1).code, 0x04);
+    }
+
+    #[test]
+    fn petscii_agrees_with_decoder() {
+        let profile = Profile::Petscii;
+        let mut term = Terminal::with_appearance(
+            profile.columns(),
+            25,
+            false,
+            profile.font(),
+            profile.palette(),
+            CursorStyle::default(),
+        );
+        term.set_profile(profile);
+        let mut decoder = PetsciiDecoder::default();
+        let mut bytes = b"ABCDEF".to_vec();
+        bytes.extend([petscii::CURSOR_LEFT, petscii::CURSOR_LEFT, petscii::DELETE]);
+        bytes.extend([petscii::INSERT, petscii::INSERT, b'X', petscii::RETURN]);
+        bytes.extend([petscii::DELETE, b'Y', petscii::CURSOR_DOWN]);
+        bytes.extend(b"GHI");
+        bytes.extend([petscii::HOME, petscii::INSERT, b'Z']);
+        for byte in &bytes {
+            term.literal(*byte);
+        }
+        decoder.input(&bytes);
+        for row in 0..3 {
+            for column in 0..profile.columns() {
+                assert_eq!(
+                    term.cell(column, row).map(|cell| cell.code),
+                    decoder.canvas().get(column, row).map(|cell| cell.code),
+                    "column {column}, row {row}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use crate::terminal::{Cell, Colour, Terminal};
+use ansiart::codepage437::CP437Char;
+use std::{fmt::Write, ops::RangeInclusive};
+
+/// A rectangle of characters selected on the terminal, from the position
+/// where it was started to the position it was extended to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Selection {
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+}
+
+// Returns the SGR sequence which selects the attributes of a cell, starting
+// from a reset so that the previous attributes don't carry over
+fn sgr(cell: &Cell) -> String {
+    let mut sgr = String::from("\x1b[0");
+    let mut true_colour = String::new();
+    match cell.fg {
+        Colour::Indexed(index) if index >= 8 => write!(sgr, ";1;{}", 30 + index - 8),
+        Colour::Indexed(index) => write!(sgr, ";{}", 30 + index),
+        Colour::Rgba([r, g, b, _]) => write!(true_colour, "\x1b[1;{r};{g};{b}t"),
+    }
+    .ok();
+    match cell.bg {
+        Colour::Indexed(index) if index >= 8 => write!(sgr, ";5;{}", 40 + index - 8),
+        Colour::Indexed(index) => write!(sgr, ";{}", 40 + index),
+        Colour::Rgba([r, g, b, _]) => write!(true_colour, "\x1b[0;{r};{g};{b}t"),
+    }
+    .ok();
+    if cell.blink && !sgr.contains(";5;") {
+        sgr.push_str(";5");
+    }
+    sgr.push('m');
+    sgr.push_str(&true_colour);
+    sgr
+}
+
+impl Selection {
+    /// Starts a selection of a single character
+    pub fn new(column: usize, row: usize) -> Selection {
+        Selection {
+            start: (column, row),
+            end: (column, row),
+        }
+    }
+
+    /// Moves the corner opposite to where the selection started
+    pub fn extend_to(&mut self, column: usize, row: usize) {
+        self.end = (column, row);
+    }
+
+    /// Returns the range of columns covered by the selection
+    pub fn columns(&self) -> RangeInclusive<usize> {
+        self.start.0.min(self.end.0)..=self.start.0.max(self.end.0)
+    }
+
+    /// Returns the range of rows covered by the selection
+    pub fn rows(&self) -> RangeInclusive<usize> {
+        self.start.1.min(self.end.1)..=self.start.1.max(self.end.1)
+    }
+
+    // Returns each row of cells within the selection, clipped to the terminal
+    fn cells<'a>(&self, term: &'a Terminal) -> Vec<Vec<&'a Cell>> {
+        self.rows()
+            .map(|row| {
+                self.columns()
+                    .filter_map(|column| term.cell(column, row))
+                    .collect::<Vec<&Cell>>()
+            })
+            .filter(|cells| !cells.is_empty())
+            .collect()
+    }
+
+    /// Returns the selected characters as Unicode text, with trailing spaces
+    /// removed from each line
+    pub fn to_unicode(&self, term: &Terminal) -> String {
+        self.cells(term)
+            .iter()
+            .map(|cells| {
+                let line: String = cells
+                    .iter()
+                    .map(|cell| CP437Char::from(cell.code).ch)
+                    .collect();
+                line.trim_end().to_string()
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// Returns the selected characters as Unicode text with SGR sequences,
+    /// and PabloDraw true colour sequences, to reproduce their colours
+    pub fn to_ansi(&self, term: &Terminal) -> String {
+        let mut ansi = String::new();
+        for (index, cells) in self.cells(term).iter().enumerate() {
+            if index > 0 {
+                ansi.push_str("\x1b[0m\n");
+            }
+            let mut previous: Option<&Cell> = None;
+            for cell in cells {
+                let attributes_changed = match previous {
+                    Some(previous) => {
+                        (previous.fg, previous.bg, previous.blink) != (cell.fg, cell.bg, cell.blink)
+                    }
+                    None => true,
+                };
+                if attributes_changed {
+                    ansi.push_str(&sgr(cell));
+                }
+                ansi.push(CP437Char::from(cell.code).ch);
+                previous = Some(cell);
+            }
+        }
+        ansi.push_str("\x1b[0m");
+        ansi
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        terminal::{Selection, Terminal},
+        Clipboard, LocalClipboard,
+    };
+
+    #[test]
+    fn copy_selection() {
+        let mut term = Terminal::new(80, 25, false);
+        term.select_graphics_rendition(&[1, 31, 44]);
+        for byte in b"Hi\x01" {
+            term.literal(*byte);
+        }
+        term.select_graphics_rendition(&[0]);
+        term.move_cursor_to(0, 1);
+        term.literal(b'!');
+        let mut selection = Selection::new(3, 1);
+        selection.extend_to(0, 0);
+        let mut clipboard = LocalClipboard::new();
+        clipboard.set_text(selection.to_unicode(&term)).unwrap();
+        assert_eq!(clipboard.text(), Some("Hi\u{263A}\n!"));
+        clipboard.set_text(selection.to_ansi(&term)).unwrap();
+        assert_eq!(
+            clipboard.text(),
+            Some("\x1b[0;1;31;44mHi\u{263A}\x1b[0;37;40m \x1b[0m\n\x1b[0;37;40m!   \x1b[0m")
+        );
+    }
+}
@@ -0,0 +1,527 @@
+use crate::{
+    capture::Capture,
+    clipboard::Clipboard,
+    crt::CrtFilter,
+    scaler::{Scaler, ASPECT_CORRECTION},
+    terminal::{MouseButton, MouseEvent, MouseModifiers, Selection, Terminal},
+    TerminalEvent, BORDER_SIZE,
+};
+use ansiart::{
+    ansiplay::{AudioOutput, Player, PlayerThread, Progress},
+    AnsiParser, Sequence,
+};
+use pixels::Pixels;
+use winit::{
+    dpi::LogicalSize,
+    event::{
+        ElementState, Event, MouseButton as WindowMouseButton, MouseScrollDelta, VirtualKeyCode,
+        WindowEvent,
+    },
+    event_loop::{ControlFlow, EventLoop, EventLoopClosed, EventLoopProxy},
+    platform::run_return::EventLoopExtRunReturn,
+    window::{Fullscreen, Window},
+};
+
+/// Messages which can be sent to a running [TerminalWindow] from any thread,
+/// `T` is the type of the host's own events
+#[derive(Debug)]
+pub enum TerminalMessage<T = ()> {
+    /// Bytes to be parsed and displayed
+    Input(Vec<u8>),
+    /// Closes the window
+    Close,
+    /// Passed to the event loop as a [TerminalEvent::User]
+    User(T),
+}
+
+/// A handle to a [TerminalWindow] which can be cloned and sent to other threads
+pub struct TerminalHandle<T: 'static = ()> {
+    proxy: EventLoopProxy<TerminalMessage<T>>,
+}
+
+impl<T: 'static> Clone for TerminalHandle<T> {
+    fn clone(&self) -> Self {
+        TerminalHandle {
+            proxy: self.proxy.clone(),
+        }
+    }
+}
+
+impl<T: 'static> TerminalHandle<T> {
+    /// Queues `bytes` to be parsed and displayed, fails if the window has closed
+    pub fn input(
+        &self,
+        bytes: impl Into<Vec<u8>>,
+    ) -> Result<(), EventLoopClosed<TerminalMessage<T>>> {
+        self.proxy.send_event(TerminalMessage::Input(bytes.into()))
+    }
+
+    /// Asks the window to close, fails if it has already closed
+    pub fn close(&self) -> Result<(), EventLoopClosed<TerminalMessage<T>>> {
+        self.proxy.send_event(TerminalMessage::Close)
+    }
+
+    /// Sends one of the host's own events to the window's event loop, fails
+    /// if the window has closed
+    pub fn send(&self, event: T) -> Result<(), EventLoopClosed<TerminalMessage<T>>> {
+        self.proxy.send_event(TerminalMessage::User(event))
+    }
+}
+
+// Returns the size of the window, including the border, that displays a frame
+// of `width` and `height` at `scale`
+pub(crate) fn window_size(
+    width: u32,
+    height: u32,
+    scale: u32,
+    aspect_correction: bool,
+) -> LogicalSize<u32> {
+    let height = if aspect_correction {
+        (height as f64 * scale as f64 * ASPECT_CORRECTION).round() as u32
+    } else {
+        height * scale
+    };
+    LogicalSize::new(
+        (width * scale) + (BORDER_SIZE * 2),
+        height + (BORDER_SIZE * 2),
+    )
+}
+
+fn finish_recording(capture: &mut Capture) {
+    match capture.stop_recording() {
+        Some(Ok(path)) => eprintln!("Saved recording to {}", path.to_string_lossy()),
+        Some(Err(err)) => eprintln!("{err}"),
+        None => {}
+    }
+}
+
+// Returns the column and row of the character under a position in the window
+fn cell_at(
+    pixels: &Pixels,
+    scaler: &Scaler,
+    crt: &Option<CrtFilter>,
+    term: &Terminal,
+    position: (f32, f32),
+) -> (usize, usize) {
+    let (x, y) = pixels
+        .window_pos_to_pixel(position)
+        .unwrap_or_else(|pos| pixels.clamp_pixel_pos(pos));
+    let (x, y) = scaler.to_frame_position(x, y);
+    match crt {
+        Some(crt) => {
+            let (x, y) = crt.to_frame_position(x, y);
+            term.cell_at(x, y)
+        }
+        None => term.cell_at(x, y),
+    }
+}
+
+// Describes the entity of music that's playing for the status line
+fn status_text(progress: &Progress) -> String {
+    let frequency = match progress.frequency {
+        Some(frequency) => format!("{frequency:.2} Hz"),
+        None => String::from("silent"),
+    };
+    // 0x0E is a pair of quavers in code page 437
+    format!(
+        " \u{e} {:>6.1}s  #{:<4} {:<12} {:>11} {:>6} ms  bytes {}-{}",
+        progress.start.as_secs_f64(),
+        progress.index,
+        progress.text,
+        frequency,
+        progress.duration.as_millis(),
+        progress.range.start,
+        progress.range.end,
+    )
+}
+
+/// A window displaying a [Terminal], constructed with a
+/// [TerminalBuilder](crate::TerminalBuilder)
+pub struct TerminalWindow<T: 'static = ()> {
+    pub(crate) event_loop: EventLoop<TerminalMessage<T>>,
+    pub(crate) window: Window,
+    pub(crate) pixels: Pixels,
+    pub(crate) term: Terminal,
+    pub(crate) scaler: Scaler,
+    pub(crate) crt: Option<CrtFilter>,
+    pub(crate) capture: Capture,
+    pub(crate) audio: bool,
+    pub(crate) status_line: bool,
+    pub(crate) playback_controls: bool,
+    pub(crate) clipboard: Box<dyn Clipboard>,
+}
+
+impl<T: 'static> TerminalWindow<T> {
+    /// Returns a handle which can feed input to the terminal from any thread
+    pub fn handle(&self) -> TerminalHandle<T> {
+        TerminalHandle {
+            proxy: self.event_loop.create_proxy(),
+        }
+    }
+
+    /// Runs the event loop, displaying the output of `parser` until the
+    /// window is closed. `term_event_loop` is called for every redraw,
+    /// unhandled keypress, and when the window is about to close.
+    pub fn run<F>(self, mut parser: AnsiParser, mut term_event_loop: F)
+    where
+        F: FnMut(&mut AnsiParser, TerminalEvent<T>, &mut Terminal),
+    {
+        let TerminalWindow {
+            mut event_loop,
+            window,
+            mut pixels,
+            mut term,
+            mut scaler,
+            mut crt,
+            mut capture,
+            audio,
+            status_line,
+            playback_controls,
+            mut clipboard,
+        } = self;
+        let (width, height) = term.get_dimensions();
+        let mut frame = vec![0; width as usize * height as usize * 4];
+        let mut filtered = match crt {
+            Some(ref crt) => {
+                let (width, height) = crt.output_dimensions();
+                vec![0; width as usize * height as usize * 4]
+            }
+            None => vec![],
+        };
+        let output = if audio {
+            AudioOutput::try_default()
+        } else {
+            AudioOutput::null()
+        };
+        let mut player = Player::new();
+        let progress = status_line.then(|| player.subscribe());
+        let mut status: Option<Progress> = None;
+        let mut player = Some(player);
+        let mut player_thread: Option<PlayerThread> = None;
+        let mut alt = false;
+        let mut ctrl = false;
+        let mut logo = false;
+        let mut shift = false;
+        let mut paused = false;
+        let mut mouse_position = (0.0, 0.0);
+        let mut selecting = false;
+        let mut selection: Option<Selection> = None;
+        let mut mouse_cell = (0, 0);
+        let mut held: Option<MouseButton> = None;
+        event_loop.run_return(|event, _target, control_flow| {
+            let mut close = false;
+            match event {
+                Event::WindowEvent {
+                    window_id,
+                    event: WindowEvent::CloseRequested,
+                    ..
+                } if window_id == window.id() => close = true,
+                Event::UserEvent(TerminalMessage::Close) => close = true,
+                Event::UserEvent(TerminalMessage::Input(bytes)) => parser.input(bytes),
+                Event::UserEvent(TerminalMessage::User(event)) => {
+                    term_event_loop(&mut parser, TerminalEvent::User(event), &mut term)
+                }
+                Event::WindowEvent {
+                    window_id,
+                    event: WindowEvent::ModifiersChanged(state),
+                    ..
+                } if window_id == window.id() => {
+                    alt = state.alt();
+                    ctrl = state.ctrl();
+                    logo = state.logo();
+                    shift = state.shift();
+                }
+                Event::WindowEvent {
+                    window_id,
+                    event: WindowEvent::CursorMoved { position, .. },
+                    ..
+                } if window_id == window.id() => {
+                    mouse_position = (position.x as f32, position.y as f32);
+                    let (column, row) = cell_at(&pixels, &scaler, &crt, &term, mouse_position);
+                    if term.is_tracking_mouse() && !shift {
+                        if mouse_cell != (column, row) {
+                            let modifiers = MouseModifiers { shift, alt, ctrl };
+                            term.report_mouse(MouseEvent::Motion(held), column, row, modifiers);
+                        }
+                    } else if let (true, Some(selection)) = (selecting, selection.as_mut()) {
+                        selection.extend_to(column, row);
+                    }
+                    mouse_cell = (column, row);
+                }
+                Event::WindowEvent {
+                    window_id,
+                    event: WindowEvent::MouseInput { state, button, .. },
+                    ..
+                } if window_id == window.id() => {
+                    let (column, row) = cell_at(&pixels, &scaler, &crt, &term, mouse_position);
+                    let reported = match button {
+                        WindowMouseButton::Left => Some(MouseButton::Left),
+                        WindowMouseButton::Middle => Some(MouseButton::Middle),
+                        WindowMouseButton::Right => Some(MouseButton::Right),
+                        WindowMouseButton::Other(_) => None,
+                    };
+                    // Holding shift selects text even when the mouse is being reported
+                    if let (true, Some(button)) = (term.is_tracking_mouse() && !shift, reported) {
+                        let event = match state {
+                            ElementState::Pressed => {
+                                held = Some(button);
+                                MouseEvent::Press(button)
+                            }
+                            ElementState::Released => {
+                                held = None;
+                                MouseEvent::Release(button)
+                            }
+                        };
+                        let modifiers = MouseModifiers { shift, alt, ctrl };
+                        term.report_mouse(event, column, row, modifiers);
+                    } else if button == WindowMouseButton::Left {
+                        match state {
+                            ElementState::Pressed => {
+                                selection = Some(Selection::new(column, row));
+                                selecting = true;
+                            }
+                            ElementState::Released => {
+                                selecting = false;
+                                // A click without dragging clears the selection
+                                if let Some(Selection { start, end }) = selection {
+                                    if start == end {
+                                        selection = None;
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                Event::WindowEvent {
+                    window_id,
+                    event: WindowEvent::MouseWheel { delta, .. },
+                    ..
+                } if window_id == window.id() && term.is_tracking_mouse() => {
+                    let up = match delta {
+                        MouseScrollDelta::LineDelta(_, y) => y > 0.0,
+                        MouseScrollDelta::PixelDelta(position) => position.y > 0.0,
+                    };
+                    let event = if up {
+                        MouseEvent::WheelUp
+                    } else {
+                        MouseEvent::WheelDown
+                    };
+                    let (column, row) = mouse_cell;
+                    term.report_mouse(event, column, row, MouseModifiers { shift, alt, ctrl });
+                }
+                Event::WindowEvent {
+                    window_id,
+                    event: WindowEvent::Resized(size),
+                    ..
+                } if window_id == window.id() => {
+                    let border = (BORDER_SIZE as f64 * window.scale_factor()).round() as u32;
+                    if scaler.resize(size.width, size.height, border) {
+                        let (output_width, output_height) = scaler.output_dimensions();
+                        pixels.resize_buffer(output_width, output_height);
+                    }
+                    pixels.resize_surface(size.width, size.height);
+                }
+                Event::WindowEvent {
+                    window_id,
+                    event:
+                        WindowEvent::KeyboardInput {
+                            input:
+                                winit::event::KeyboardInput {
+                                    virtual_keycode: Some(key_code),
+                                    state: ElementState::Pressed,
+                                    ..
+                                },
+                            ..
+                        },
+                    ..
+                } if window_id == window.id() => match key_code {
+                    #[cfg(target_os = "macos")]
+                    VirtualKeyCode::W if logo => close = true,
+                    #[cfg(target_os = "linux")]
+                    #[cfg(target_os = "windows")]
+                    VirtualKeyCode::F4 if alt => close = true,
+                    VirtualKeyCode::Return if alt => match window.fullscreen() {
+                        Some(_) => window.set_fullscreen(None),
+                        None => window.set_fullscreen(Some(Fullscreen::Borderless(None))),
+                    },
+                    VirtualKeyCode::C if ctrl || logo => {
+                        if let Some(ref selection) = selection {
+                            let text = if shift {
+                                selection.to_ansi(&term)
+                            } else {
+                                selection.to_unicode(&term)
+                            };
+                            if let Err(err) = clipboard.set_text(text) {
+                                eprintln!("{err}");
+                            }
+                        }
+                    }
+                    VirtualKeyCode::Escape if selection.is_some() => selection = None,
+                    VirtualKeyCode::F12 => match capture.screenshot(&frame) {
+                        Ok(path) => eprintln!("Saved screenshot to {}", path.to_string_lossy()),
+                        Err(err) => eprintln!("{err}"),
+                    },
+                    VirtualKeyCode::F11 => match capture.toggle_recording() {
+                        Ok(path) if capture.is_recording() => {
+                            eprintln!("Recording to {}", path.to_string_lossy())
+                        }
+                        Ok(path) => eprintln!("Saved recording to {}", path.to_string_lossy()),
+                        Err(err) => eprintln!("{err}"),
+                    },
+                    VirtualKeyCode::Space if playback_controls => paused = !paused,
+                    VirtualKeyCode::Plus | VirtualKeyCode::Equals | VirtualKeyCode::NumpadAdd
+                        if playback_controls =>
+                    {
+                        parser.increase_baud_rate();
+                    }
+                    VirtualKeyCode::Minus | VirtualKeyCode::NumpadSubtract if playback_controls => {
+                        parser.decrease_baud_rate();
+                    }
+                    VirtualKeyCode::Right if playback_controls => parser.skip_to_end(),
+                    VirtualKeyCode::R if playback_controls => {
+                        if let Some(thread) = player_thread.take() {
+                            thread.abort().expect("abort");
+                            player = Some(thread.join().expect("join"));
+                        }
+                        parser.restart();
+                        term.reset();
+                        paused = false;
+                    }
+                    _ => match player_thread {
+                        Some(ref thread) if thread.is_blocking() => {
+                            thread.interrupt().expect("Thread Error")
+                        }
+                        _ => {
+                            term_event_loop(
+                                &mut parser,
+                                TerminalEvent::Keypress {
+                                    key_code,
+                                    alt,
+                                    logo,
+                                    shift,
+                                },
+                                &mut term,
+                            );
+                        }
+                    },
+                },
+                Event::RedrawRequested(_) => {
+                    term_event_loop(&mut parser, TerminalEvent::RedrawRequested, &mut term);
+                    term.next_frame(&mut frame);
+                    if let Some(ref selection) = selection {
+                        term.draw_selection(&mut frame, selection);
+                    }
+                    if let Some(ref status) = status {
+                        term.draw_status(&mut frame, status_text(status).as_bytes());
+                    }
+                    // Recorded as screenshots are taken, with the selection
+                    // and status line, but before the CRT filter and scaling
+                    capture.record(&frame);
+                    match crt {
+                        Some(ref mut crt) => {
+                            crt.draw(&frame, &mut filtered);
+                            scaler.draw(&filtered, pixels.get_frame());
+                        }
+                        None => scaler.draw(&frame, pixels.get_frame()),
+                    }
+                    pixels.render().expect("Unable to render");
+                }
+                _ => {}
+            }
+            if close {
+                term_event_loop(&mut parser, TerminalEvent::CloseRequested, &mut term);
+                finish_recording(&mut capture);
+                if let Some(thread) = player_thread.take() {
+                    thread.abort().expect("abort");
+                    thread.join().expect("join");
+                }
+                *control_flow = ControlFlow::Exit;
+                return;
+            }
+            if let Some(ref thread) = player_thread {
+                if thread.finished_playing().expect("Thread error") {
+                    player = Some(
+                        player_thread
+                            .take()
+                            .expect("Player Thread")
+                            .join()
+                            .expect("Thread Error"),
+                    );
+                }
+            }
+            if let Some(ref progress) = progress {
+                if let Some(latest) = progress.try_iter().last() {
+                    status = Some(latest);
+                }
+                if player_thread.is_none() {
+                    status = None;
+                }
+            }
+            // Music in the background only holds up drawing once its queue is full
+            match player_thread {
+                Some(ref thread) if thread.is_blocking() => {}
+                _ if paused => {}
+                _ => {
+                    for sequence in parser.by_ref() {
+                        match sequence {
+                            Sequence::Literal(byte) => term.literal(byte),
+                            Sequence::CarriageReturn => term.carriage_return(),
+                            Sequence::LineFeed => term.line_feed(),
+                            Sequence::Tab => term.tab(),
+                            Sequence::CursorUp(amount) => term.cursor_up(amount),
+                            Sequence::CursorDown(amount) => term.cursor_down(amount),
+                            Sequence::CursorForward(amount) => term.cursor_forward(amount),
+                            Sequence::CursorBack(amount) => term.cursor_back(amount),
+                            Sequence::CursorPosition { row, column } => {
+                                term.move_cursor_to(column, row);
+                            }
+                            Sequence::SetScreenMode(value) => term.set_screen_mode(value),
+                            Sequence::ResetScreenMode(value) => term.reset_screen_mode(value),
+                            Sequence::EraseDisplay(value) => term.erase_display(value),
+                            Sequence::EraseInLine(value) => term.erase_in_line(value),
+                            Sequence::DeviceStatusReport(value) => term.device_status_report(value),
+                            Sequence::DeviceAttributes => term.device_attributes(),
+                            Sequence::SelectCursorStyle(value) => term.select_cursor_style(value),
+                            Sequence::FontSelection { slot, font } => term.select_font(slot, font),
+                            Sequence::SelectGraphicsRendition(values) => {
+                                term.select_graphics_rendition(&values)
+                            }
+                            Sequence::SavePosition => term.save_cursor_position(),
+                            Sequence::RestorePosition => term.restore_cursor_position(),
+                            Sequence::TrueColourBg { r, g, b } => term.rgb_bg(r, g, b),
+                            Sequence::TrueColourFg { r, g, b } => term.rgb_fg(r, g, b),
+                            Sequence::SetPaletteColours(colours) => {
+                                term.set_palette_colours(&colours)
+                            }
+                            Sequence::ResetPalette(indexes) => term.reset_palette(&indexes),
+                            Sequence::Music(music) => {
+                                let music = match player_thread {
+                                    Some(ref thread) => thread.queue(music).err(),
+                                    None => Some(music),
+                                };
+                                // The thread may have finished since it was last checked
+                                if let Some(music) = music {
+                                    if let Some(thread) = player_thread.take() {
+                                        player = Some(thread.join().expect("Thread Error"));
+                                    }
+                                    player_thread = Some(
+                                        PlayerThread::new(
+                                            player.take().expect("Player"),
+                                            &output,
+                                            music,
+                                        )
+                                        .expect("Thread error"),
+                                    );
+                                }
+                                break;
+                            }
+                            Sequence::Update => break,
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            window.request_redraw();
+        });
+    }
+}
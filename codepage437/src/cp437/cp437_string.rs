@@ -103,7 +103,7 @@ impl CP437String {
         self.chars.last()
     }
 
-    fn chars(&self) -> Iter<CP437Char> {
+    fn chars(&self) -> Iter<'_, CP437Char> {
         self.chars.iter()
     }
 }
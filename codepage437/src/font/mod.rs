@@ -2,11 +2,13 @@ mod draw_font;
 mod font_error;
 /// Raw binary data for various bitmap fonts
 pub mod raw;
+mod syncterm;
 pub use draw_font::DrawFont;
 use ega_palette::{Rgba, BLACK_RGBA, WHITE_RGBA};
 pub use font_error::FontError;
 use image::{DynamicImage, ImageBuffer, Pixel};
 use std::{fs, path::Path};
+use syncterm::syncterm_font;
 
 /// Structure to hold, read, write, and generate rgba data for fonts
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -32,6 +34,31 @@ impl Font {
         }
     }
 
+    /// Returns the font SyncTERM selects with `number` in `ESC[n;m D`, from
+    /// the fonts bundled in [raw]
+    pub fn syncterm(number: usize) -> Result<Font, FontError> {
+        match syncterm_font(number) {
+            Some(bytes) => Font::try_from(bytes),
+            None => Err(FontError::CouldNotLocateFont),
+        }
+    }
+
+    /// Returns a copy of the font with each glyph stretched or squashed to
+    /// `height` rows, so that an 8 pixel font can be used with a 16 pixel one
+    pub fn with_height(&self, height: usize) -> Font {
+        let mut bytes = Vec::with_capacity(256 * height);
+        for glyph in self.bytes.chunks_exact(self.height) {
+            for row in 0..height {
+                bytes.push(glyph[row * self.height / height]);
+            }
+        }
+        Font {
+            bytes,
+            width: self.width,
+            height,
+        }
+    }
+
     /// Attemptes to read an image constructed from a 16x16 grid, with each glyph 8 pixels wide
     pub fn read_image(path: impl AsRef<Path>) -> Result<Font, FontError> {
         match image::io::Reader::open(path) {
@@ -159,7 +186,7 @@ impl TryFrom<&[u8]> for Font {
     type Error = FontError;
 
     fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
-        if bytes.len() % 256 != 0 {
+        if !bytes.len().is_multiple_of(256) {
             return Err(FontError::IllegalFontSize);
         }
         let height = bytes.len() / 256;
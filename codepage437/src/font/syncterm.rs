@@ -0,0 +1,24 @@
+use crate::font::raw;
+
+// The fonts SyncTERM selects by number with `ESC[n;m D`, for those which are
+// bundled, the C128 fonts are drawn with the closest C64 glyphs
+pub(crate) fn syncterm_font(number: usize) -> Option<&'static [u8]> {
+    let bytes: &[u8] = match number {
+        0 => raw::CP437_F16,
+        5 | 25 | 27 => raw::CP866_F16,
+        17 | 18 => raw::CP850_F16,
+        19 | 28 => raw::CP865_F16,
+        26 => raw::CP437_F16,
+        32 | 34 => raw::PETSCII_UNSHIFTED_F08,
+        33 | 35 => raw::PETSCII_SHIFTED_F08,
+        36 => raw::ATASCII_F08,
+        37 => raw::P0T_NOODLE_F16,
+        38 => raw::MO_SOUL_F16,
+        39 => raw::MICRO_KNIGHT_PLUS_F16,
+        40 => raw::TOPAZ_PLUS_A1200_F16,
+        41 => raw::MICRO_KNIGHT_F16,
+        42 => raw::TOPAZ_A1200_F16,
+        _ => return None,
+    };
+    Some(bytes)
+}
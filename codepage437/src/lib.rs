@@ -2,5 +2,5 @@
 pub mod ascii;
 mod cp437;
 mod font;
-pub use cp437::{CP437Error, CP437String};
+pub use cp437::{CP437Char, CP437Error, CP437String};
 pub use font::{raw, DrawFont, Font, FontError};
@@ -149,7 +149,7 @@ impl EgaPalette {
 
     /// Constructs a new palette with a complette complement of possible EGA values 0-63
     pub fn all() -> EgaPalette {
-        let indexes: Vec<u8> = (0..64).into_iter().collect();
+        let indexes: Vec<u8> = (0..64).collect();
         EgaPalette::new(indexes.as_slice())
     }
 
@@ -2,19 +2,14 @@ use crate::SauceError;
 use serde::{Deserialize, Serialize};
 use std::{fmt, str::FromStr};
 
-#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize, Default)]
 pub enum AspectRatio {
     None,
+    #[default]
     Modern,
     Legacy,
 }
 
-impl Default for AspectRatio {
-    fn default() -> AspectRatio {
-        AspectRatio::Modern
-    }
-}
-
 impl fmt::Display for AspectRatio {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -2,8 +2,9 @@ use crate::{sauce::FileType, SauceError};
 use serde::{Deserialize, Serialize};
 pub use std::{fmt, str::FromStr};
 
-#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize, Default)]
 pub enum DataType {
+    #[default]
     None,
     Character,
     Bitmap,
@@ -15,12 +16,6 @@ pub enum DataType {
     Executable,
 }
 
-impl Default for DataType {
-    fn default() -> Self {
-        DataType::None
-    }
-}
-
 impl fmt::Display for DataType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
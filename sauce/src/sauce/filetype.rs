@@ -3,8 +3,9 @@ use regex::Regex;
 use serde::{de, Deserialize, Serialize};
 pub use std::{fmt, str::FromStr};
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
 pub enum FileType {
+    #[default]
     None,
     Ascii,
     Ansi,
@@ -72,12 +73,6 @@ pub enum FileType {
     Sqz,
 }
 
-impl Default for FileType {
-    fn default() -> Self {
-        FileType::None
-    }
-}
-
 impl fmt::Display for FileType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
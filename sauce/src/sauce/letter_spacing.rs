@@ -2,19 +2,14 @@ use crate::SauceError;
 use serde::{de, Deserialize, Serialize};
 pub use std::{fmt, str::FromStr};
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
 pub enum LetterSpacing {
     None,
+    #[default]
     EightPixels,
     NinePixels,
 }
 
-impl Default for LetterSpacing {
-    fn default() -> LetterSpacing {
-        LetterSpacing::EightPixels
-    }
-}
-
 impl fmt::Display for LetterSpacing {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {